@@ -0,0 +1,94 @@
+use num_bigint::BigInt;
+use std::fmt::{Display, Formatter};
+
+/// Result of a puzzle part, printed the way the puzzle expects it to be submitted.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Answer {
+    Int(i128),
+    /// Integers beyond `i128`, only ever built through `From<BigInt>` so that
+    /// a value fitting `Int` always is one and compares equal to it.
+    Big(BigInt),
+    Text(String),
+    Coord(i64, i64),
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Int(value) => write!(f, "{}", value),
+            Answer::Big(value) => write!(f, "{}", value),
+            Answer::Text(text) => write!(f, "{}", text),
+            Answer::Coord(x, y) => write!(f, "{},{}", x, y),
+        }
+    }
+}
+
+macro_rules! int_answer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(value: $t) -> Self {
+                    Answer::Int(value as i128)
+                }
+            }
+        )*
+    };
+}
+
+int_answer!(i32, i64, u64, usize, i128);
+
+impl From<BigInt> for Answer {
+    fn from(value: BigInt) -> Self {
+        match i128::try_from(&value) {
+            Ok(value) => Answer::Int(value),
+            Err(_) => Answer::Big(value),
+        }
+    }
+}
+
+impl From<String> for Answer {
+    fn from(value: String) -> Self {
+        Answer::Text(value)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(value: &str) -> Self {
+        Answer::Text(value.to_string())
+    }
+}
+
+// lets tests compare against plain literals, e.g. `assert_eq!(11, part1(input()))`
+impl PartialEq<Answer> for i64 {
+    fn eq(&self, other: &Answer) -> bool {
+        *other == Answer::Int(*self as i128)
+    }
+}
+
+impl PartialEq<Answer> for &str {
+    fn eq(&self, other: &Answer) -> bool {
+        matches!(other, Answer::Text(text) if text == self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!("65601038650482", Answer::from(65601038650482_i64).to_string());
+        assert_eq!("4,6,3", Answer::from("4,6,3").to_string());
+        assert_eq!("6,1", Answer::Coord(6, 1).to_string());
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(Answer::from(11_usize), Answer::from(11_i32));
+        assert_eq!(11, Answer::from(11_u64));
+        assert_ne!("11", Answer::from(11_i64));
+        assert_eq!(11, Answer::from(BigInt::from(11)));
+        let big = BigInt::from(i128::MAX) + 1_u8;
+        assert_eq!(Answer::Big(big.clone()), Answer::from(big));
+    }
+}
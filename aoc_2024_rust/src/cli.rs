@@ -0,0 +1,26 @@
+use std::env;
+use std::str::FromStr;
+
+// Command line: `cargo run -- [day] [--flag ...] [--option value ...]`
+
+/// Day to run, if the first argument is a number; all days run otherwise.
+pub fn day() -> Option<usize> {
+    env::args().nth(1).and_then(|v| usize::from_str(&v).ok())
+}
+
+pub fn flag(name: &str) -> bool {
+    env::args().skip(1).any(|arg| arg == name)
+}
+
+/// Values of every `--name value` or `--name=value` argument.
+pub fn options(name: &str) -> Vec<String> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let prefix = format!("{}=", name);
+    args.iter().enumerate().filter_map(|(idx, arg)| {
+        if arg == name {
+            args.get(idx + 1).cloned()
+        } else {
+            arg.strip_prefix(&prefix).map(|v| v.to_string())
+        }
+    }).collect()
+}
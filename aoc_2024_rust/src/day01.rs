@@ -1,82 +1,232 @@
-use std::collections::HashMap;
+use crate::answer::Answer;
+use crate::parse::{number, ParseError};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
 use std::fs;
+use std::io::{BufRead, BufReader};
 use std::str::FromStr;
 
 
 pub fn print() {
-    let s = fs::read_to_string("data/01.txt").unwrap();
-    let input = Input::from_str(s.as_str()).expect("cannot parse input");
+    let file = fs::File::open("data/01.txt").unwrap();
+    let input = Input::from_reader(BufReader::new(file)).expect("cannot parse input");
 
+    // everything below borrows the one copy of the lists
     println!("Day 1");
-    println!("Part 1 {}", part1(input.clone()));
-    println!("Part 2 {}", part2(input.clone()));
+    println!("Part 1 {}", part1(&input));
+    println!("Part 2 {}", part2(&input));
+
+    println!("Distance");
+    print!("{}", table(&distance_matrix(&input)));
+    println!("Similarity");
+    print!("{}", table(&similarity_matrix(&input)));
+
+    let gaps = distances(&input.columns[0], &input.columns[1]);
+    if let Some(median) = median_gap(&gaps) {
+        println!("Median gap {}", median);
+    }
+    let histogram = histogram(&gaps, 10)
+        .iter()
+        .map(|(bucket, count)| format!("{}: {}", bucket, count))
+        .collect::<Vec<_>>();
+    println!("Distance histogram {}", histogram.join(", "));
+    let shared = top_shared(&input.columns[0], &input.columns[1], 3)
+        .iter()
+        .map(|s| format!("{} ({}x{})", s.id, s.left, s.right))
+        .collect::<Vec<_>>();
+    println!("Top shared {}", shared.join(", "));
 }
 
-#[derive(Clone)]
 struct Input {
-    left: Vec<i32>,
-    right: Vec<i32>,
+    // every column sorted ascending
+    columns: Vec<Vec<i64>>,
 }
 
 #[derive(Debug)]
-enum InputError {}
+enum InputError {
+    Io(std::io::Error),
+    Parse(ParseError),
+}
 
-impl FromStr for Input {
-    type Err = InputError;
+impl Display for InputError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::Io(err) => write!(f, "cannot read input: {}", err),
+            InputError::Parse(err) => write!(f, "{}", err),
+        }
+    }
+}
 
-    fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let mut left = vec![];
-        let mut right = vec![];
-        for line in input.lines() {
-            let items = line.split("   ").collect::<Vec<_>>();
-            assert_eq!(items.len(), 2, "Error split: {}", line);
-
-            if let Ok(value) = i32::from_str(items[0]) {
-                left.push(value);
-            } else {
-                panic!("Error parsing left {}", line)
+impl From<std::io::Error> for InputError {
+    fn from(err: std::io::Error) -> Self {
+        InputError::Io(err)
+    }
+}
+
+impl From<ParseError> for InputError {
+    fn from(err: ParseError) -> Self {
+        InputError::Parse(err)
+    }
+}
+
+impl Input {
+    // reads line by line into a reused buffer, but every value is still kept: memory
+    // grows by 8 bytes per value, so an input that doesn't fit in memory would need
+    // an external sort for the distances; the first line decides how many columns there are
+    fn from_reader<R: BufRead>(mut reader: R) -> Result<Self, InputError> {
+        let mut columns: Vec<Vec<i64>> = vec![];
+        let mut line = String::new();
+        let mut row: Vec<i64> = vec![];
+        while reader.read_line(&mut line)? > 0 {
+            row.clear();
+            for item in line.split_whitespace() {
+                row.push(number(item)?);
+            }
+            if columns.is_empty() {
+                columns = vec![vec![]; row.len()];
             }
-            if let Ok(value) = i32::from_str(items[1]) {
-                right.push(value);
-            } else {
-                panic!("Error parsing right {}", line);
+            if !row.is_empty() && row.len() != columns.len() {
+                return Err(ParseError::Malformed(line.trim().to_string()).into());
             }
+            columns.iter_mut().zip(row.iter()).for_each(|(column, v)| column.push(*v));
+            line.clear();
         }
+        if columns.len() < 2 {
+            return Err(ParseError::Malformed("expected at least two columns".to_string()).into());
+        }
+        // sorted once in place, the distances pair up sorted values and the counts don't care
+        columns.iter_mut().for_each(|column| column.sort_unstable());
         Ok(Input {
-            left,
-            right,
+            columns
         })
     }
 }
 
+impl FromStr for Input {
+    type Err = InputError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Input::from_reader(input.as_bytes())
+    }
+}
+
+// gaps between sorted columns, paired up smallest to smallest
+fn distances(a: &[i64], b: &[i64]) -> Vec<i64> {
+    a.iter().zip(b.iter()).map(|(l, r)| {
+        (*l - *r).abs()
+    }).collect()
+}
+
+fn counts(values: &[i64]) -> HashMap<i64, i64> {
+    values.iter().fold(HashMap::new(), |mut acc, v| {
+        *acc.entry(*v).or_insert(0) += 1;
+        acc
+    })
+}
+
+// every value of `a` weighted by how often it occurs in `b`
+fn similarity(a: &[i64], b_counts: &HashMap<i64, i64>) -> i64 {
+    a.iter().fold(0_i64, |acc, v| {
+        acc + *v * b_counts.get(v).copied().unwrap_or(0)
+    })
+}
+
+fn part1(input: &Input) -> Answer {
+    let res = distances(&input.columns[0], &input.columns[1]).iter().sum::<i64>();
+
+    res.into()
+}
+
 
-fn part1(mut input: Input) -> i32 {
-    input.left.sort_by_key(|v| *v);
-    input.right.sort_by_key(|v| *v);
+fn part2(input: &Input) -> Answer {
+    let index = counts(&input.columns[1]);
 
-    let res = input.left.iter().zip(input.right.iter()).fold(0, |acc, (l, r)| {
-        acc + (*l - *r).abs()
-    });
+    let res = similarity(&input.columns[0], &index);
 
-    res
+    res.into()
 }
 
+/// Total distance between every pair of columns, `[i][j]` compares column `i` with `j`.
+fn distance_matrix(input: &Input) -> Vec<Vec<i64>> {
+    input.columns.iter().map(|a| {
+        input.columns.iter().map(|b| distances(a, b).iter().sum()).collect()
+    }).collect()
+}
 
-fn part2(input: Input) -> i32 {
-    let index = input.right.iter().fold(HashMap::new(), |mut acc, v| {
-        if let Some(value) = acc.get(v) {
-            acc.insert(v, value + 1);
-        } else {
-            acc.insert(v, 1);
+/// Similarity of column `i` against the counts of column `j`; not symmetric.
+fn similarity_matrix(input: &Input) -> Vec<Vec<i64>> {
+    let counts = input.columns.iter().map(|c| counts(c)).collect::<Vec<_>>();
+    input.columns.iter().map(|a| {
+        counts.iter().map(|b| similarity(a, b)).collect()
+    }).collect()
+}
+
+fn table(matrix: &[Vec<i64>]) -> String {
+    let width = matrix.iter().flatten()
+        .map(|v| v.to_string().len())
+        .chain([matrix.len().to_string().len()])
+        .max()
+        .unwrap_or(1);
+    let mut out = format!("{:>w$}", "", w = width);
+    for j in 0..matrix.len() {
+        out.push_str(&format!(" {:>w$}", j, w = width));
+    }
+    out.push('\n');
+    for (i, row) in matrix.iter().enumerate() {
+        out.push_str(&format!("{:>w$}", i, w = width));
+        for v in row {
+            out.push_str(&format!(" {:>w$}", v, w = width));
         }
+        out.push('\n');
+    }
+    out
+}
+
+fn median_gap(distances: &[i64]) -> Option<f64> {
+    if distances.is_empty() {
+        return None;
+    }
+    let mut gaps = distances.to_vec();
+    let mid = gaps.len() / 2;
+    let (lower, upper, _) = gaps.select_nth_unstable(mid);
+    let upper = *upper as f64;
+    if !distances.len().is_multiple_of(2) {
+        Some(upper)
+    } else {
+        let below = *lower.iter().max().expect("lower half is not empty") as f64;
+        Some((below + upper) / 2.0)
+    }
+}
+
+/// Number of distances per bucket of `width`, keyed by the bucket start.
+fn histogram(distances: &[i64], width: i64) -> Vec<(i64, usize)> {
+    distances.iter().fold(BTreeMap::new(), |mut acc, d| {
+        *acc.entry(d - d.rem_euclid(width)).or_insert(0) += 1;
         acc
-    });
+    }).into_iter().collect()
+}
 
-    let res = input.left.iter().fold(0, |acc, v| {
-        acc + v * index.get(v).copied().unwrap_or(0)
-    });
+#[derive(Debug, Eq, PartialEq)]
+struct Shared {
+    id: i64,
+    left: i64,
+    right: i64,
+}
 
-    res
+/// IDs present in both lists, ordered by the number of pairs they form.
+fn top_shared(a: &[i64], b: &[i64], k: usize) -> Vec<Shared> {
+    let left = counts(a);
+    let right = counts(b);
+    let mut shared = left.iter().filter_map(|(id, l)| {
+        right.get(id).map(|r| Shared {
+            id: *id,
+            left: *l,
+            right: *r,
+        })
+    }).collect::<Vec<_>>();
+    shared.sort_by_key(|s| (-(s.left * s.right), s.id));
+    shared.truncate(k);
+    shared
 }
 
 
@@ -94,13 +244,43 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let res = part1(input());
+        let res = part1(&input());
         assert_eq!(11, res)
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(input());
+        let res = part2(&input());
         assert_eq!(31, res)
     }
+
+    #[test]
+    fn test_whitespace() {
+        let input = Input::from_str("3\t4\r\n-4 3\n\n").expect("cannot parse input");
+        assert_eq!(vec![vec![-4, 3], vec![3, 4]], input.columns);
+        assert!(Input::from_str("3 4\n3 4 5\n").is_err());
+        assert!(Input::from_str("3 4 5\n3 4\n").is_err());
+        assert!(Input::from_str("3\n4\n").is_err());
+    }
+
+    #[test]
+    fn test_metrics() {
+        let input = input();
+        let gaps = distances(&input.columns[0], &input.columns[1]);
+        assert_eq!(Some(1.5), median_gap(&gaps));
+        assert_eq!(Some(5.0), median_gap(&[5]));
+        assert_eq!(vec![(0, 3), (2, 2), (4, 1)], histogram(&gaps, 2));
+        assert_eq!(
+            vec![Shared { id: 3, left: 3, right: 3 }],
+            top_shared(&input.columns[0], &input.columns[1], 1)
+        );
+    }
+
+    #[test]
+    fn test_matrix() {
+        let input = Input::from_str("3 4 1\n4 3 2\n2 5 3\n").expect("cannot parse input");
+        assert_eq!(vec![vec![9, 7, 5], vec![7, 12, 3], vec![5, 3, 6]], similarity_matrix(&input));
+        assert_eq!(vec![vec![0, 3, 3], vec![3, 0, 6], vec![3, 6, 0]], distance_matrix(&input));
+        assert_eq!("  0 1 2\n0 0 3 3\n1 3 0 6\n2 3 6 0\n", table(&distance_matrix(&input)));
+    }
 }
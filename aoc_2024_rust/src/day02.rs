@@ -1,3 +1,6 @@
+use crate::answer::Answer;
+use crate::cli;
+use crate::parse::{list, number, ParseError};
 use std::fs;
 use std::str::FromStr;
 
@@ -9,6 +12,14 @@ pub fn print() {
     println!("Day 2");
     println!("Part 1 {}", part1(input.clone()));
     println!("Part 2 {}", part2(input.clone()));
+    for spec in cli::options("--policy") {
+        let policy = Policy::from_str(&spec).expect("cannot parse policy");
+        let (safe, dampened) = classify(&input, &policy);
+        println!("Policy {}: {} safe, {} with one removal", spec, safe, dampened);
+    }
+    if cli::flag("--diagnostics") {
+        diagnostics(&input, &Policy::default()).iter().for_each(|line| println!("{}", line));
+    }
 }
 
 #[derive(Clone)]
@@ -16,61 +27,226 @@ struct Input {
     reports: Vec<Vec<i32>>,
 }
 
-#[derive(Debug)]
-enum InputError {}
-
 impl FromStr for Input {
-    type Err = InputError;
+    type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let reports = input.lines().map(|line| {
-            line.split(" ").map(|v| i32::from_str(v).expect("cannot parse number in input")).collect::<Vec<_>>()
-        }).collect::<Vec<Vec<i32>>>();
+        let reports = input.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| list(line, ' '))
+            .collect::<Result<Vec<Vec<i32>>, _>>()?;
         Ok(Input {
             reports
         })
     }
 }
 
-fn part1(input: Input) -> i32 {
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Direction {
+    Increasing,
+    Decreasing,
+    Either,
+}
+
+/// What makes a report safe. The default is the puzzle's rule:
+/// strictly increasing or decreasing by 1 to 3 per step.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Policy {
+    min_step: i32,
+    max_step: i32,
+    direction: Direction,
+    plateaus: bool,
+    max_span: Option<i32>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            min_step: 1,
+            max_step: 3,
+            direction: Direction::Either,
+            plateaus: false,
+            max_span: None,
+        }
+    }
+}
+
+// e.g. `step=1..3,dir=inc,plateaus,span=10`; anything left out keeps the default
+impl FromStr for Policy {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut policy = Policy::default();
+        for part in input.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part.split_once('=').unwrap_or((part, ""));
+            match key {
+                "step" => {
+                    let (min, max) = value.split_once("..")
+                        .ok_or(ParseError::Malformed(part.to_string()))?;
+                    policy.min_step = number(min)?;
+                    policy.max_step = number(max)?;
+                }
+                "dir" => {
+                    policy.direction = match value {
+                        "inc" => Direction::Increasing,
+                        "dec" => Direction::Decreasing,
+                        "either" => Direction::Either,
+                        _ => return Err(ParseError::Malformed(part.to_string())),
+                    }
+                }
+                "plateaus" => policy.plateaus = true,
+                "span" => policy.max_span = Some(number(value)?),
+                _ => return Err(ParseError::Malformed(part.to_string())),
+            }
+        }
+        Ok(policy)
+    }
+}
+
+impl Policy {
+    fn dirs(&self) -> &'static [i32] {
+        match self.direction {
+            Direction::Increasing => &[1],
+            Direction::Decreasing => &[-1],
+            Direction::Either => &[1, -1],
+        }
+    }
+
+    fn step_ok(&self, from: i32, to: i32, dir: i32) -> bool {
+        let diff = (to - from) * dir;
+        if diff == 0 {
+            self.plateaus
+        } else {
+            diff > 0 && (self.min_step..=self.max_step).contains(&diff)
+        }
+    }
+
+    fn span_ok(&self, first: i32, last: i32) -> bool {
+        self.max_span.is_none_or(|span| (last - first).abs() <= span)
+    }
+}
+
+fn part1(input: Input) -> Answer {
     let res = input.reports.iter().filter(|line| {
-        check_line(line, 0)
-    }).count() as i32;
+        dampen(line, 0, &Policy::default()).is_some()
+    }).count();
 
-    res
+    res.into()
 }
 
 
-fn part2(input: Input) -> i32 {
-    let res = input.reports.into_iter().filter(|line| {
-        let rev_line = line.iter().rev().copied().collect::<Vec<i32>>();
-        check_line(line, 1) || check_line(&rev_line, 1)
-    }).count() as i32;
+fn part2(input: Input) -> Answer {
+    let res = input.reports.iter().filter(|line| {
+        dampen(line, 1, &Policy::default()).is_some()
+    }).count();
+
+    res.into()
+}
 
-    res
+/// Number of reports that are safe as they are, and with at most one level removed.
+fn classify(input: &Input, policy: &Policy) -> (usize, usize) {
+    input.reports.iter().fold((0, 0), |(safe, dampened), line| {
+        let removed = min_removals(line, policy).len();
+        (safe + usize::from(removed == 0), dampened + usize::from(removed <= 1))
+    })
 }
 
-fn check_line(line: &[i32], max_errors: i32) -> bool {
-    let mut prev = 0;
-    let mut next = 1;
-    let mut prev_diff = 0;
-    let mut num_errors = 0;
-    while next < line.len() {
-        let diff = line[next] - line[prev];
-        if diff.abs() < 1 || diff.abs() > 3 || diff * prev_diff < 0 {
-            if num_errors < max_errors {
-                num_errors += 1;
-                //skip this one, maybe next one will work
-                next += 1;
-                continue;
+/// Levels to remove to make the report safe, if no more than `max_errors` are needed.
+fn dampen(line: &[i32], max_errors: usize, policy: &Policy) -> Option<Vec<usize>> {
+    Some(min_removals(line, policy)).filter(|removed| removed.len() <= max_errors)
+}
+
+// Keeps the longest safe subsequence, trying every direction and first level;
+// longest[i] is the most levels that can be kept ending with level i.
+// The kept levels are monotonic, so their span is the gap between the first and last one.
+fn min_removals(line: &[i32], policy: &Policy) -> Vec<usize> {
+    let mut best: Option<Vec<usize>> = None;
+    for &dir in policy.dirs() {
+        for first in 0..line.len() {
+            let mut longest = vec![0; line.len()];
+            let mut prev: Vec<Option<usize>> = vec![None; line.len()];
+            longest[first] = 1;
+            for next in first + 1..line.len() {
+                if !policy.span_ok(line[first], line[next]) {
+                    continue;
+                }
+                for curr in first..next {
+                    if longest[curr] > 0
+                        && policy.step_ok(line[curr], line[next], dir)
+                        && longest[curr] + 1 > longest[next] {
+                        longest[next] = longest[curr] + 1;
+                        prev[next] = Some(curr);
+                    }
+                }
+            }
+            let mut kept = vec![false; line.len()];
+            let mut last = (0..line.len()).rev().max_by_key(|&idx| longest[idx]);
+            while let Some(idx) = last {
+                kept[idx] = true;
+                last = prev[idx];
+            }
+            let removed = (0..line.len()).filter(|&idx| !kept[idx]).collect::<Vec<_>>();
+            if best.as_ref().is_none_or(|b| removed.len() < b.len()) {
+                best = Some(removed);
             }
-            return false;
         }
-        prev_diff = diff;
-        prev = next;
-        next += 1;
     }
-    true
+    best.unwrap_or_default()
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum Failure {
+    Plateau,
+    DirectionFlip,
+    StepTooSmall,
+    StepTooLarge,
+    SpanTooLarge,
+}
+
+/// First pair of adjacent levels `(idx, idx + 1)` that makes the report unsafe.
+fn diagnose(line: &[i32], policy: &Policy) -> Option<(usize, Failure)> {
+    let dir = match policy.direction {
+        Direction::Increasing => 1,
+        Direction::Decreasing => -1,
+        Direction::Either => line.windows(2)
+            .map(|w| (w[1] - w[0]).signum())
+            .find(|&d| d != 0 || !policy.plateaus)
+            .unwrap_or(0),
+    };
+    line.windows(2).enumerate().find_map(|(idx, w)| {
+        let diff = w[1] - w[0];
+        if diff == 0 {
+            Some((idx, Failure::Plateau)).filter(|_| !policy.plateaus)
+        } else if diff.signum() != dir {
+            Some((idx, Failure::DirectionFlip))
+        } else if diff.abs() < policy.min_step {
+            Some((idx, Failure::StepTooSmall))
+        } else if diff.abs() > policy.max_step {
+            Some((idx, Failure::StepTooLarge))
+        } else if !policy.span_ok(line[0], w[1]) {
+            Some((idx, Failure::SpanTooLarge))
+        } else {
+            None
+        }
+    })
+}
+
+fn diagnostics(input: &Input, policy: &Policy) -> Vec<String> {
+    input.reports.iter().filter_map(|line| {
+        let (idx, failure) = diagnose(line, policy)?;
+        let levels = line.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(" ");
+        let reason = match failure {
+            Failure::Plateau => "no change",
+            Failure::DirectionFlip => "direction flip",
+            Failure::StepTooSmall => "step too small",
+            Failure::StepTooLarge => "step too large",
+            Failure::SpanTooLarge => "span too large",
+        };
+        Some(format!(
+            "{}: {} between levels {} and {} ({} -> {}), remove {:?}",
+            levels, reason, idx, idx + 1, line[idx], line[idx + 1], min_removals(line, policy)
+        ))
+    }).collect()
 }
 
 #[cfg(test)]
@@ -96,4 +272,54 @@ mod tests {
         let res = part2(input());
         assert_eq!(5, res)
     }
+
+    fn dampen_default(line: &[i32], max_errors: usize) -> Option<Vec<usize>> {
+        dampen(line, max_errors, &Policy::default())
+    }
+
+    #[test]
+    fn test_dampen() {
+        assert_eq!(Some(vec![]), dampen_default(&[7, 6, 4, 2, 1], 0));
+        assert_eq!(None, dampen_default(&[1, 2, 7, 8, 9], 1));
+        assert_eq!(Some(vec![0, 1]), dampen_default(&[1, 2, 7, 8, 9], 2));
+        assert_eq!(Some(vec![2]), dampen_default(&[1, 3, 2, 4, 5], 1));
+        assert_eq!(Some(vec![0]), dampen_default(&[9, 1, 2, 3, 4], 1));
+        assert_eq!(Some(vec![1, 3]), dampen_default(&[1, 9, 2, 0, 3, 4], 2));
+    }
+
+    fn diagnose_default(line: &[i32]) -> Option<(usize, Failure)> {
+        diagnose(line, &Policy::default())
+    }
+
+    #[test]
+    fn test_diagnose() {
+        assert_eq!(None, diagnose_default(&[7, 6, 4, 2, 1]));
+        assert_eq!(Some((1, Failure::StepTooLarge)), diagnose_default(&[1, 2, 7, 8, 9]));
+        assert_eq!(Some((1, Failure::DirectionFlip)), diagnose_default(&[1, 3, 2, 4, 5]));
+        assert_eq!(Some((2, Failure::Plateau)), diagnose_default(&[8, 6, 4, 4, 1]));
+        assert_eq!(
+            "1 3 2 4 5: direction flip between levels 1 and 2 (3 -> 2), remove [2]",
+            diagnostics(&input(), &Policy::default())[2]
+        );
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy = Policy::from_str("step=1..5,dir=inc,plateaus,span=6").unwrap();
+        assert_eq!(Policy {
+            min_step: 1,
+            max_step: 5,
+            direction: Direction::Increasing,
+            plateaus: true,
+            max_span: Some(6),
+        }, policy);
+        assert!(Policy::from_str("dir=up").is_err());
+        assert_eq!((2, 5), classify(&input(), &Policy::default()));
+        assert_eq!(Some(vec![]), dampen(&[1, 1, 5, 6], 0, &policy));
+        assert_eq!(Some(vec![3]), dampen(&[1, 1, 5, 9], 1, &policy));
+        assert_eq!(Some((2, Failure::SpanTooLarge)), diagnose(&[1, 1, 5, 9], &policy));
+        assert_eq!(Some((0, Failure::DirectionFlip)), diagnose(&[7, 6, 4, 2, 1], &policy));
+        let policy = Policy::from_str("step=2..3").unwrap();
+        assert_eq!(Some((1, Failure::StepTooSmall)), diagnose(&[1, 3, 4], &policy));
+    }
 }
@@ -1,6 +1,11 @@
+use crate::answer::Answer;
+use crate::cli;
 use regex::Regex;
+use std::fmt::{Display, Formatter};
 use std::fs;
+use std::io::Read;
 use std::str::FromStr;
+use std::time::Instant;
 
 pub fn print() {
     let s = fs::read_to_string("data/03.txt").unwrap();
@@ -9,6 +14,18 @@ pub fn print() {
     println!("Day 3");
     println!("Part 1 {}", part1(input.clone()));
     println!("Part 2 {}", part2(input.clone()));
+
+    if cli::flag("--trace") {
+        Interpreter::extended().run(&input.input).iter().for_each(|step| println!("{}", step));
+    }
+    for path in cli::options("--memory") {
+        let file = fs::File::open(&path).expect("cannot open memory dump");
+        let totals = scan(file, CHUNK_SIZE).expect("cannot read memory dump");
+        println!("{}: {} all, {} enabled", path, totals.all, totals.enabled);
+    }
+    if cli::flag("--bench") {
+        bench(&input.input.repeat(1000));
+    }
 }
 
 #[derive(Clone)]
@@ -29,34 +46,318 @@ impl FromStr for Input {
     }
 }
 
-fn part1(input: Input) -> i32 {
-    let re: Regex = Regex::new("mul\\((\\d+),(\\d+)\\)").unwrap();
+fn part1(input: Input) -> Answer {
+    let mut interpreter = Interpreter::new();
+    interpreter.register("mul", 2, Op::Value(|args| args[0] * args[1]));
+
+    total(&interpreter.run(&input.input)).into()
+}
+
 
-    re.captures_iter(input.input.as_str()).map(|cap| {
-        let left = cap.get(1).and_then(|v| i32::from_str(v.as_str()).ok()).unwrap();
-        let right = cap.get(2).and_then(|v| i32::from_str(v.as_str()).ok()).unwrap();
-        vec![left, right]
-    }).fold(0, |acc, v| { acc + v[0] * v[1] })
+fn part2(input: Input) -> Answer {
+    total(&Interpreter::puzzle().run(&input.input)).into()
 }
 
+#[derive(Clone, Copy)]
+enum Op {
+    Value(fn(&[i64]) -> i64),
+    Enable,
+    Disable,
+}
+
+struct Instruction {
+    name: String,
+    arity: usize,
+    op: Op,
+}
 
-fn part2(input: Input) -> i32 {
-    let re: Regex = Regex::new("(?<mul>mul\\((\\d+),(\\d+)\\))|(?<do>do\\(\\))|(?<dont>don't\\(\\))").unwrap();
+/// Finds `name(a,b,...)` calls in corrupted memory and runs them in order.
+/// Operands are 1 to 3 digits; anything that doesn't match exactly is skipped.
+struct Interpreter {
+    instructions: Vec<Instruction>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct Step {
+    offset: usize,
+    text: String,
+    value: i64,
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:>6}: {} = {}", self.offset, self.text, self.value)
+    }
+}
+
+impl Interpreter {
+    fn new() -> Self {
+        Interpreter {
+            instructions: vec![],
+        }
+    }
+
+    /// `mul` with `do()` and `don't()`, as in the puzzle.
+    fn puzzle() -> Self {
+        let mut interpreter = Interpreter::new();
+        interpreter.register("mul", 2, Op::Value(|args| args[0] * args[1]));
+        interpreter.register("do", 0, Op::Enable);
+        interpreter.register("don't", 0, Op::Disable);
+        interpreter
+    }
+
+    fn extended() -> Self {
+        let mut interpreter = Interpreter::puzzle();
+        interpreter.register("add", 2, Op::Value(|args| args[0] + args[1]));
+        interpreter.register("sub", 2, Op::Value(|args| args[0] - args[1]));
+        interpreter
+    }
+
+    /// Adds an instruction, or replaces the one with the same name.
+    fn register(&mut self, name: &str, arity: usize, op: Op) {
+        self.instructions.retain(|i| i.name != name);
+        self.instructions.push(Instruction {
+            name: name.to_string(),
+            arity,
+            op,
+        });
+    }
+
+    /// Every well-formed instruction with its byte offset and operands.
+    fn tokenize<'a>(&'a self, memory: &str) -> Vec<(usize, &'a Instruction, Vec<i64>)> {
+        let bytes = memory.as_bytes();
+        let mut tokens = vec![];
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let token = self.instructions.iter().find_map(|instruction| {
+                let start = pos + instruction.name.len();
+                if !bytes[pos..].starts_with(instruction.name.as_bytes()) || bytes.get(start) != Some(&b'(') {
+                    return None;
+                }
+                operands(bytes, start + 1, instruction.arity).map(|(args, end)| (instruction, args, end))
+            });
+            match token {
+                Some((instruction, args, end)) => {
+                    tokens.push((pos, instruction, args));
+                    pos = end;
+                }
+                None => pos += 1,
+            }
+        }
+        tokens
+    }
+
+    /// Trace of the executed instructions; value instructions are skipped after `don't()`.
+    fn run(&self, memory: &str) -> Vec<Step> {
+        let mut enabled = true;
+        self.tokenize(memory).into_iter().filter_map(|(offset, instruction, args)| {
+            let value = match instruction.op {
+                Op::Value(f) if enabled => f(&args),
+                Op::Value(_) => return None,
+                Op::Enable => {
+                    enabled = true;
+                    0
+                }
+                Op::Disable => {
+                    enabled = false;
+                    0
+                }
+            };
+            let args = args.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+            Some(Step {
+                offset,
+                text: format!("{}({})", instruction.name, args.join(",")),
+                value,
+            })
+        }).collect()
+    }
+}
+
+// `arity` comma separated operands of 1 to 3 digits and the closing parenthesis,
+// starting at `pos`; returns the operands and the position after the parenthesis
+fn operands(bytes: &[u8], mut pos: usize, arity: usize) -> Option<(Vec<i64>, usize)> {
+    let mut args = Vec::with_capacity(arity);
+    for idx in 0..arity {
+        if idx > 0 {
+            if bytes.get(pos) != Some(&b',') {
+                return None;
+            }
+            pos += 1;
+        }
+        let len = bytes[pos.min(bytes.len())..].iter().take(4).take_while(|b| b.is_ascii_digit()).count();
+        if !(1..=3).contains(&len) {
+            return None;
+        }
+        args.push(bytes[pos..pos + len].iter().fold(0, |acc, b| acc * 10 + (b - b'0') as i64));
+        pos += len;
+    }
+    if bytes.get(pos) != Some(&b')') {
+        return None;
+    }
+    Some((args, pos + 1))
+}
 
-    let (sum, _) = re.captures_iter(input.input.as_str()).fold((0, true), |(acc, state), cap| {
-        if cap.name("do").is_some() {
-            (acc, true)
-        } else if cap.name("dont").is_some() {
-            (acc, false)
-        } else if cap.name("mul").is_some() && state {
-            let left = cap.get(2).and_then(|m| i32::from_str(m.as_str()).ok()).unwrap();
-            let right = cap.get(3).and_then(|m| i32::from_str(m.as_str()).ok()).unwrap();
-            (acc + (left * right), state)
+fn total(trace: &[Step]) -> i64 {
+    trace.iter().map(|step| step.value).sum()
+}
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Sums of every `mul`, and of the ones enabled by `do()`/`don't()`.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+struct Totals {
+    all: i64,
+    enabled: i64,
+}
+
+// how much of `mul(a,b)`, `do()` or `don't()` has been read so far;
+// operands count their digits, which are kept in the scanner
+#[derive(Clone, Copy, Default)]
+enum State {
+    #[default]
+    Idle,
+    M,
+    Mu,
+    Mul,
+    Left(u8),
+    Right(u8),
+    D,
+    Do,
+    DoOpen,
+    Don,
+    DonQuote,
+    Dont,
+    DontOpen,
+}
+
+/// Byte at a time `mul`/`do()`/`don't()` matcher without regex or buffering,
+/// so input can be fed in chunks that split instructions anywhere.
+#[derive(Default)]
+struct Scanner {
+    state: State,
+    disabled: bool,
+    left: i64,
+    right: i64,
+    totals: Totals,
+}
+
+impl Scanner {
+    fn feed(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            self.state = match self.step(b) {
+                Some(state) => state,
+                // 'm' and 'd' only start an instruction, so a failed match
+                // can only restart at the byte that broke it
+                None => self.step_idle(b),
+            };
+        }
+    }
+
+    fn step_idle(&mut self, b: u8) -> State {
+        match b {
+            b'm' => State::M,
+            b'd' => State::D,
+            _ => State::Idle,
+        }
+    }
+
+    fn step(&mut self, b: u8) -> Option<State> {
+        let next = match (self.state, b) {
+            (State::Idle, _) => return None,
+            (State::M, b'u') => State::Mu,
+            (State::Mu, b'l') => State::Mul,
+            (State::Mul, b'(') => {
+                self.left = 0;
+                State::Left(0)
+            }
+            (State::Left(n), b'0'..=b'9') if n < 3 => {
+                self.left = self.left * 10 + (b - b'0') as i64;
+                State::Left(n + 1)
+            }
+            (State::Left(n), b',') if n > 0 => {
+                self.right = 0;
+                State::Right(0)
+            }
+            (State::Right(n), b'0'..=b'9') if n < 3 => {
+                self.right = self.right * 10 + (b - b'0') as i64;
+                State::Right(n + 1)
+            }
+            (State::Right(n), b')') if n > 0 => {
+                let product = self.left * self.right;
+                self.totals.all += product;
+                if !self.disabled {
+                    self.totals.enabled += product;
+                }
+                State::Idle
+            }
+            (State::D, b'o') => State::Do,
+            (State::Do, b'(') => State::DoOpen,
+            (State::DoOpen, b')') => {
+                self.disabled = false;
+                State::Idle
+            }
+            (State::Do, b'n') => State::Don,
+            (State::Don, b'\'') => State::DonQuote,
+            (State::DonQuote, b't') => State::Dont,
+            (State::Dont, b'(') => State::DontOpen,
+            (State::DontOpen, b')') => {
+                self.disabled = true;
+                State::Idle
+            }
+            _ => return None,
+        };
+        Some(next)
+    }
+}
+
+/// Streams `reader` through a `Scanner` in `chunk_size` pieces.
+fn scan<R: Read>(mut reader: R, chunk_size: usize) -> std::io::Result<Totals> {
+    let mut scanner = Scanner::default();
+    let mut buf = vec![0; chunk_size];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(scanner.totals),
+            Ok(n) => scanner.feed(&buf[..n]),
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+// the original regex solution, kept as the reference for `bench`
+fn regex_totals(memory: &str) -> Totals {
+    let re = Regex::new("mul\\((\\d{1,3}),(\\d{1,3})\\)|(do\\(\\))|(don't\\(\\))").unwrap();
+    let (totals, _) = re.captures_iter(memory).fold((Totals::default(), true), |(mut totals, state), cap| {
+        if cap.get(3).is_some() {
+            (totals, true)
+        } else if cap.get(4).is_some() {
+            (totals, false)
         } else {
-            (acc, state)
+            let left = i64::from_str(&cap[1]).unwrap();
+            let right = i64::from_str(&cap[2]).unwrap();
+            totals.all += left * right;
+            if state {
+                totals.enabled += left * right;
+            }
+            (totals, state)
         }
     });
-    sum
+    totals
+}
+
+fn bench(memory: &str) {
+    let start = Instant::now();
+    let expected = regex_totals(memory);
+    println!("Regex {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let interpreter = Interpreter::puzzle();
+    assert_eq!(expected.enabled, total(&interpreter.run(memory)));
+    println!("Interpreter {:?}", start.elapsed());
+
+    let start = Instant::now();
+    assert_eq!(expected, scan(memory.as_bytes(), CHUNK_SIZE).unwrap());
+    println!("Scanner {:?}", start.elapsed());
 }
 
 #[cfg(test)]
@@ -83,4 +384,29 @@ mod tests {
         let res = part2(input);
         assert_eq!(48, res)
     }
+
+    #[test]
+    fn test_trace() {
+        let trace = Interpreter::extended().run("mul(2,4)add(1,1234)don't()sub(9,3)do()sub(3,9)");
+        let steps = trace.iter().map(|s| (s.offset, s.text.as_str(), s.value)).collect::<Vec<_>>();
+        assert_eq!(vec![(0, "mul(2,4)", 8), (19, "don't()", 0), (34, "do()", 0), (38, "sub(3,9)", -6)], steps);
+    }
+
+    #[test]
+    fn test_register() {
+        let mut interpreter = Interpreter::extended();
+        interpreter.register("neg", 1, Op::Value(|args| -args[0]));
+        interpreter.register("mad", 3, Op::Value(|args| args[0] * args[1] + args[2]));
+        assert_eq!(5, total(&interpreter.run("neg(7)?mad(2,5,2)mad(1,2)")));
+    }
+
+    #[test]
+    fn test_scan() {
+        let memory = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))mmul(1,1000)mul(3,3)";
+        let expected = Totals { all: 170, enabled: 57 };
+        assert_eq!(expected, regex_totals(memory));
+        for chunk_size in 1..=16 {
+            assert_eq!(expected, scan(memory.as_bytes(), chunk_size).unwrap());
+        }
+    }
 }
@@ -1,3 +1,8 @@
+use crate::answer::Answer;
+use crate::cli;
+use crate::parse::ParseError;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
 use std::fs;
 use std::str::FromStr;
 
@@ -8,6 +13,19 @@ pub fn print() {
     println!("Day 4");
     println!("Part 1 {}", part1(input.clone()));
     println!("Part 2 {}", part2(input.clone()));
+
+    if cli::flag("--render") {
+        let colour = cli::flag("--colour");
+        print!("{}", render(&input.map, &find_words(&input.map, &["XMAS"]), colour));
+        let stencil = Stencil::from_str("M.S\n.A.\nM.S").unwrap();
+        print!("{}", render(&input.map, &find_stencil(&input.map, &stencil), colour));
+    }
+
+    let words = cli::options("--word");
+    let words = words.iter().map(|w| w.as_str()).collect::<Vec<_>>();
+    for m in find_words(&input.map, &words) {
+        println!("{} at {},{} going {}", words[m.pattern], m.x, m.y, m.orientation);
+    }
 }
 
 #[derive(Clone)]
@@ -29,101 +47,247 @@ impl FromStr for Input {
     }
 }
 
-fn part1(input: Input) -> i32 {
-    let paths = [
-        vec![(0, 1), (0, 2), (0, 3)],
-        vec![(0, -1), (0, -2), (0, -3)],
-        vec![(1, 0), (2, 0), (3, 0)],
-        vec![(-1, 0), (-2, 0), (-3, 0)],
-        vec![(1, 1), (2, 2), (3, 3)],
-        vec![(1, -1), (2, -2), (3, -3)],
-        vec![(-1, 1), (-2, 2), (-3, 3)],
-        vec![(-1, -1), (-2, -2), (-3, -3)],
-    ];
-    // let mut starts: Vec<(usize, usize)> = vec![];
-    let x_max = input.map.len();
-    let y_max = input.map[0].len();
-    let chars = "XMAS".chars().collect::<Vec<_>>();
-    let mut num_found = 0;
-    input.map.iter().enumerate().for_each(|(x, row)| {
-        row.iter().enumerate().for_each(|(y, letter)| {
-            if *letter == 'X' {
-                // starts.push((x, y));
-                paths.iter().for_each(|path| {
-                    let mut matching = true;
-                    let mut idx = 0;
-                    while matching && idx < path.len() {
-                        let (x_i, y_i) = path[idx];
-                        let x = x as i32 + x_i;
-                        let y = y as i32 + y_i;
-                        if x >= 0 && y >= 0
-                            && x < x_max as i32 && y < y_max as i32
-                            && input.map[x as usize][y as usize] == chars[idx + 1]
-                        {
-                            matching = true;
-                        } else {
-                            matching = false;
-                            break;
-                        }
-                        idx += 1;
-                    }
-                    if matching {
-                        num_found += 1;
+fn part1(input: Input) -> Answer {
+    find_words(&input.map, &["XMAS"]).len().into()
+}
+
+
+fn part2(input: Input) -> Answer {
+    let stencil = Stencil::from_str("M.S\n.A.\nM.S").unwrap();
+    find_stencil(&input.map, &stencil).len().into()
+}
+
+// (row, column) steps, clockwise from north
+const DIRECTIONS: [(i32, i32); 8] = [(-1, 0), (-1, 1), (0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1)];
+const DIRECTION_NAMES: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+enum Orientation {
+    /// Index into `DIRECTIONS` the word is read in.
+    Direction(usize),
+    /// Clockwise quarter turns applied to the stencil.
+    Rotation(usize),
+}
+
+impl Display for Orientation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Orientation::Direction(dir) => write!(f, "{}", DIRECTION_NAMES[*dir]),
+            Orientation::Rotation(turns) => write!(f, "{} degrees", turns * 90),
+        }
+    }
+}
+
+/// Occurrence of the `pattern`-th word or stencil. `(x, y)` is the first letter
+/// of a word, or the top left corner of the rotated stencil; `cells` are the
+/// matched letters in word order, or row by row for a stencil.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct Match {
+    pattern: usize,
+    x: usize,
+    y: usize,
+    orientation: Orientation,
+    cells: Vec<(usize, usize)>,
+}
+
+/// Aho-Corasick automaton: a trie of the words with failure links,
+/// so all words are matched in a single pass over a line of letters.
+struct Automaton {
+    goto: Vec<HashMap<char, usize>>,
+    fail: Vec<usize>,
+    // (word, length) of every word ending in a state, including through failure links
+    out: Vec<Vec<(usize, usize)>>,
+}
+
+impl Automaton {
+    fn new(words: &[&str]) -> Self {
+        let mut automaton = Automaton {
+            goto: vec![HashMap::new()],
+            fail: vec![0],
+            out: vec![vec![]],
+        };
+        for (idx, word) in words.iter().enumerate().filter(|(_, w)| !w.is_empty()) {
+            let mut state = 0;
+            for c in word.chars() {
+                state = match automaton.goto[state].get(&c) {
+                    Some(&next) => next,
+                    None => {
+                        automaton.goto.push(HashMap::new());
+                        automaton.fail.push(0);
+                        automaton.out.push(vec![]);
+                        let next = automaton.goto.len() - 1;
+                        automaton.goto[state].insert(c, next);
+                        next
                     }
-                })
+                };
             }
-        })
-    });
-    num_found
-}
-
-
-fn part2(input: Input) -> i32 {
-    let paths = [
-        vec![(-1, -1), (0, 0), (1, 1)],
-        vec![(1, -1), (0, 0), (-1, 1)],
-        vec![(-1, 1), (0, 0), (1, -1)],
-        vec![(1, 1), (0, 0), (-1, -1)],
-    ];
-    // let mut starts: Vec<(usize, usize)> = vec![];
-    let x_max = input.map.len();
-    let y_max = input.map[0].len();
-    let chars = "MAS".chars().collect::<Vec<_>>();
-    let mut num_found = 0;
-    input.map.iter().enumerate().for_each(|(x, row)| {
-        row.iter().enumerate().for_each(|(y, letter)| {
-            if *letter == 'A' {
-                // starts.push((x, y));
-                let all_match = paths.iter().filter(|path| {
-                    let mut matching = true;
-                    let mut idx = 0;
-                    let mut str = String::new();
-                    while matching && idx < path.len() {
-                        let (x_i, y_i) = path[idx];
-                        let x = x as i32 + x_i;
-                        let y = y as i32 + y_i;
-                        if x >= 0 && y >= 0
-                            && x < x_max as i32 && y < y_max as i32
-                            && input.map[x as usize][y as usize] == chars[idx]
-                        {
-                            matching = true;
-                            str.push(input.map[x as usize][y as usize]);
-                        } else {
-                            matching = false;
-                            break;
-                        }
-
-                        idx += 1;
-                    }
-                    matching
-                }).count() > 1;
-                if all_match {
-                    num_found += 1;
+            automaton.out[state].push((idx, word.chars().count()));
+        }
+        let mut queue = automaton.goto[0].values().copied().collect::<VecDeque<_>>();
+        while let Some(state) = queue.pop_front() {
+            let edges = automaton.goto[state].iter().map(|(&c, &next)| (c, next)).collect::<Vec<_>>();
+            for (c, next) in edges {
+                let fail = automaton.next(automaton.fail[state], c);
+                automaton.fail[next] = fail;
+                let inherited = automaton.out[fail].clone();
+                automaton.out[next].extend(inherited);
+                queue.push_back(next);
+            }
+        }
+        automaton
+    }
+
+    fn next(&self, mut state: usize, c: char) -> usize {
+        loop {
+            if let Some(&next) = self.goto[state].get(&c) {
+                return next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.fail[state];
+        }
+    }
+}
+
+/// Every occurrence of any of the words, read in all eight directions.
+fn find_words(map: &[Vec<char>], words: &[&str]) -> Vec<Match> {
+    let automaton = Automaton::new(words);
+    let (x_max, y_max) = (map.len() as i32, map.first().map_or(0, |row| row.len()) as i32);
+    let inside = |x: i32, y: i32| x >= 0 && y >= 0 && x < x_max && y < y_max;
+    let mut matches = vec![];
+    for (dir, &(dx, dy)) in DIRECTIONS.iter().enumerate() {
+        // every line in this direction starts at a cell whose predecessor is off the grid
+        for (x0, y0) in (0..x_max).flat_map(|x| (0..y_max).map(move |y| (x, y))) {
+            if inside(x0 - dx, y0 - dy) {
+                continue;
+            }
+            let (mut x, mut y, mut state) = (x0, y0, 0);
+            while inside(x, y) {
+                state = automaton.next(state, map[x as usize][y as usize]);
+                for &(pattern, len) in &automaton.out[state] {
+                    let back = len as i32 - 1;
+                    let (x_start, y_start) = (x - dx * back, y - dy * back);
+                    matches.push(Match {
+                        pattern,
+                        x: x_start as usize,
+                        y: y_start as usize,
+                        orientation: Orientation::Direction(dir),
+                        cells: (0..len as i32)
+                            .map(|k| ((x_start + dx * k) as usize, (y_start + dy * k) as usize))
+                            .collect(),
+                    });
                 }
+                (x, y) = (x + dx, y + dy);
             }
+        }
+    }
+    matches.sort_by_key(|m| (m.x, m.y, m.pattern, m.orientation));
+    matches
+}
+
+/// Small grid of letters to look for, `.` matches any letter.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Stencil {
+    cells: Vec<Vec<Option<char>>>,
+}
+
+impl FromStr for Stencil {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let cells = input.lines().map(|line| {
+            line.trim_end().chars().map(|c| Some(c).filter(|&c| c != '.')).collect::<Vec<_>>()
+        }).collect::<Vec<_>>();
+        if cells.is_empty() || cells[0].is_empty() || cells.iter().any(|row| row.len() != cells[0].len()) {
+            return Err(ParseError::Malformed(input.to_string()));
+        }
+        Ok(Stencil {
+            cells
         })
-    });
-    num_found
+    }
+}
+
+impl Stencil {
+    fn rotate(&self) -> Stencil {
+        let (h, w) = (self.cells.len(), self.cells[0].len());
+        Stencil {
+            cells: (0..w).map(|r| (0..h).map(|c| self.cells[h - 1 - c][r]).collect()).collect(),
+        }
+    }
+
+    /// The distinct clockwise rotations with their number of quarter turns.
+    fn rotations(&self) -> Vec<(usize, Stencil)> {
+        let mut rotations: Vec<(usize, Stencil)> = vec![(0, self.clone())];
+        for turns in 1..4 {
+            let next = rotations[turns - 1].1.rotate();
+            rotations.push((turns, next));
+        }
+        let mut distinct: Vec<(usize, Stencil)> = vec![];
+        for (turns, stencil) in rotations {
+            if !distinct.iter().any(|(_, s)| *s == stencil) {
+                distinct.push((turns, stencil));
+            }
+        }
+        distinct
+    }
+
+    /// Grid cells of the letters (not the wildcards) when placed at `(x, y)`.
+    fn cells_at(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        self.cells.iter().enumerate().flat_map(|(r, row)| {
+            row.iter().enumerate().filter(|(_, cell)| cell.is_some()).map(move |(c, _)| (x + r, y + c))
+        }).collect()
+    }
+
+    fn matches_at(&self, map: &[Vec<char>], x: usize, y: usize) -> bool {
+        self.cells.iter().enumerate().all(|(r, row)| {
+            row.iter().enumerate().all(|(c, cell)| {
+                cell.is_none_or(|letter| map.get(x + r).and_then(|l| l.get(y + c)) == Some(&letter))
+            })
+        })
+    }
+}
+
+/// Every placement of the stencil in any of its rotations.
+fn find_stencil(map: &[Vec<char>], stencil: &Stencil) -> Vec<Match> {
+    let y_max = map.first().map_or(0, |row| row.len());
+    stencil.rotations().iter().flat_map(|(turns, rotated)| {
+        (0..map.len()).flat_map(move |x| (0..y_max).map(move |y| (x, y)))
+            .filter(|&(x, y)| rotated.matches_at(map, x, y))
+            .map(|(x, y)| Match {
+                pattern: 0,
+                x,
+                y,
+                orientation: Orientation::Rotation(*turns),
+                cells: rotated.cells_at(x, y),
+            })
+    }).collect()
+}
+
+const COLOURS: [&str; 6] = ["31", "32", "33", "34", "35", "36"];
+
+/// The grid with every letter outside the matches replaced by '.', like the
+/// puzzle's illustrations. With `colour`, each match gets its own ANSI colour;
+/// where matches overlap the later one wins.
+fn render(map: &[Vec<char>], matches: &[Match], colour: bool) -> String {
+    let mut owner: Vec<Vec<Option<usize>>> = map.iter().map(|row| vec![None; row.len()]).collect();
+    for (idx, m) in matches.iter().enumerate() {
+        m.cells.iter().for_each(|&(x, y)| owner[x][y] = Some(idx));
+    }
+    let mut out = String::new();
+    for (row, owners) in map.iter().zip(owner.iter()) {
+        for (letter, owner) in row.iter().zip(owners.iter()) {
+            match owner {
+                Some(idx) if colour => {
+                    out.push_str(&format!("\x1b[{}m{}\x1b[0m", COLOURS[idx % COLOURS.len()], letter))
+                }
+                Some(_) => out.push(*letter),
+                None => out.push('.'),
+            }
+        }
+        out.push('\n');
+    }
+    out
 }
 
 #[cfg(test)]
@@ -149,4 +313,49 @@ mod tests {
         let res = part2(input());
         assert_eq!(9, res)
     }
+
+    #[test]
+    fn test_find_words() {
+        let map = input().map;
+        let matches = find_words(&map, &["XMAS", "SAMX", "MAS", "AS"]);
+        let count = |pattern| matches.iter().filter(|m| m.pattern == pattern).count();
+        assert_eq!((18, 18), (count(0), count(1)));
+        assert_eq!(
+            Some(&Match {
+                pattern: 0,
+                x: 0,
+                y: 4,
+                orientation: Orientation::Direction(3),
+                cells: vec![(0, 4), (1, 5), (2, 6), (3, 7)],
+            }),
+            matches.iter().find(|m| m.pattern == 0)
+        );
+        assert!(count(3) > count(2));
+        assert_eq!(0, find_words(&map, &[]).len());
+    }
+
+    #[test]
+    fn test_find_stencil() {
+        let map = input().map;
+        let stencil = Stencil::from_str("XMAS").unwrap();
+        assert_eq!(4, stencil.rotations().len());
+        assert_eq!(8, find_stencil(&map, &stencil).len());
+        let symmetric = Stencil::from_str("S.S\n.A.\nS.S").unwrap();
+        assert_eq!(1, symmetric.rotations().len());
+        assert!(Stencil::from_str("MS\nM").is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let map = Input::from_str("..X...\n.SAMX.\n.A..A.\nXMAS.S\n.X....\n").unwrap().map;
+        let matches = find_words(&map, &["XMAS"]);
+        assert_eq!(4, matches.len());
+        assert_eq!("..X...\n.SAMX.\n.A..A.\nXMAS.S\n.X....\n", render(&map, &matches, false));
+
+        let stencil = Stencil::from_str("M.S\n.A.\nM.S").unwrap();
+        let matches = find_stencil(&input().map, &stencil);
+        assert_eq!(vec![(0, 1), (0, 3), (1, 2), (2, 1), (2, 3)], matches[0].cells);
+        let rendered = render(&input().map, &matches, true);
+        assert!(rendered.starts_with(".\x1b[31mM\x1b[0m.\x1b[31mS\x1b[0m......"));
+    }
 }
@@ -1,5 +1,7 @@
-use std::cmp::Ordering;
-use std::collections::HashSet;
+use crate::answer::Answer;
+use crate::cli;
+use crate::parse::{list, section_pair, ParseError};
+use crate::rule_graph::{OrderError, RuleGraph};
 use std::fs;
 use std::str::FromStr;
 
@@ -8,8 +10,22 @@ pub fn print() {
     let input = Input::from_str(s.as_str()).expect("cannot parse input");
 
     println!("Day 5");
+    if let Some(cycle) = graph(&input).cycle() {
+        println!("Rules contain a {}", cycle);
+    }
     println!("Part 1 {}", part1(input.clone()));
     println!("Part 2 {}", part2(input.clone()));
+
+    for (idx, err) in reorder(&input).1 {
+        println!("Update {} left out of part 2: {}", idx, err);
+    }
+    let ambiguous = ambiguous(&input);
+    if !ambiguous.is_empty() {
+        println!("Updates with more than one valid order {:?}", ambiguous);
+    }
+    if cli::flag("--explain") {
+        explain(&input).iter().for_each(|line| println!("{}", line));
+    }
 }
 
 #[derive(Clone)]
@@ -18,26 +34,24 @@ struct Input {
     pages: Vec<Vec<u8>>,
 }
 
-#[derive(Debug)]
-enum InputError {}
-
 impl FromStr for Input {
-    type Err = InputError;
+    type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let lines = input.lines().collect::<Vec<&str>>();
-        let rules_strs = lines.iter().take_while(|l| !l.is_empty()).collect::<Vec<_>>();
-        let book_strs: Vec<&str> = lines[rules_strs.len() + 1..].to_vec();
-        let rules: Vec<Vec<u8>> = rules_strs.iter()
-            .map(|s|
-                s.split('|')
-                    .map(|s| u8::from_str(s).unwrap())
-                    .collect()
-            )
-            .collect::<Vec<Vec<u8>>>();
-        let pages = book_strs.iter().take_while(|l| !l.is_empty()).map(|s| {
-            s.split(',').map(|s| u8::from_str(s).unwrap()).collect::<Vec<u8>>()
-        }).collect::<Vec<Vec<u8>>>();
+        let (rules_str, pages_str) = section_pair(input)?;
+        let rules = rules_str.lines()
+            .map(|s| {
+                let rule: Vec<u8> = list(s, '|')?;
+                if rule.len() == 2 {
+                    Ok(rule)
+                } else {
+                    Err(ParseError::Malformed(s.to_string()))
+                }
+            })
+            .collect::<Result<Vec<Vec<u8>>, _>>()?;
+        let pages = pages_str.lines()
+            .map(|s| list(s, ','))
+            .collect::<Result<Vec<Vec<u8>>, _>>()?;
         Ok(Input {
             rules,
             pages,
@@ -45,39 +59,90 @@ impl FromStr for Input {
     }
 }
 
-fn common(input: Input) -> (i32, i32) {
-    let idx = input.rules.iter().map(|rule| {
-        (rule[0], rule[1])
-    }).collect::<HashSet<(u8, u8)>>();
-    let (correct, incorrect) = input.pages.iter().fold((0, 0), |(mut correct, mut incorrect), page| {
-        let mut sorted_page = page.clone();
-        sorted_page.sort_by(|l, r| {
-            if idx.contains(&(*l, *r)) {
-                Ordering::Less
-            } else if idx.contains(&(*r, *l)) {
-                Ordering::Greater
-            } else {
-                Ordering::Equal
-            }
-        });
-        if sorted_page.eq(page) {
-            correct += page[page.len() / 2] as i32;
-        } else {
-            incorrect += sorted_page[sorted_page.len() / 2] as i32;
+fn graph(input: &Input) -> RuleGraph<u8> {
+    RuleGraph::new(input.rules.iter().map(|rule| (rule[0], rule[1])))
+}
+
+// middle pages of the updates breaking no rule, even if the rules allow other orders
+fn correct(input: &Input) -> i64 {
+    let graph = graph(input);
+    input.pages.iter()
+        .filter(|page| graph.violations(page).is_empty())
+        .map(|page| page[page.len() / 2] as i64)
+        .sum()
+}
+
+// middle pages of the incorrect updates once ordered, and the updates that can't be
+fn reorder(input: &Input) -> (i64, Vec<(usize, OrderError<u8>)>) {
+    let graph = graph(input);
+    let mut unordered = vec![];
+    let mut sum = 0;
+    for (idx, page) in input.pages.iter().enumerate() {
+        if graph.violations(page).is_empty() {
+            continue;
+        }
+        match graph.order(page) {
+            Ok(sorted) => sum += sorted.order[sorted.order.len() / 2] as i64,
+            Err(err) => unordered.push((idx, err)),
+        }
+    }
+    (sum, unordered)
+}
+
+/// Indices of the updates the rules don't fully order.
+fn ambiguous(input: &Input) -> Vec<usize> {
+    let graph = graph(input);
+    input.pages.iter().enumerate()
+        .filter(|(_, page)| graph.order(page).is_ok_and(|order| !order.unique))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Rules broken by each incorrect update, with the moves and swaps that fix it.
+/// Swaps are counted towards the verified order, so they are minimal when it is unique.
+fn explain(input: &Input) -> Vec<String> {
+    let graph = graph(input);
+    input.pages.iter().enumerate().filter_map(|(idx, page)| {
+        let violations = graph.violations(page);
+        if violations.is_empty() {
+            return None;
         }
-        (correct, incorrect)
-    });
-    (correct, incorrect)
+        let broken = violations.iter()
+            .map(|&(i, j)| format!("{}|{} ({} at {}, {} at {})", page[j], page[i], page[j], j, page[i], i))
+            .collect::<Vec<_>>();
+        let swaps = graph.order(page).map_or(0, |order| swaps(page, &order.order));
+        Some(format!(
+            "update {} breaks {}; moves {}, swaps {}",
+            idx, broken.join(", "), graph.min_moves(page), swaps
+        ))
+    }).collect()
 }
 
-fn part1(input: Input) -> i32 {
-    let (correct, _incorrect) = common(input);
-    correct
+// fewest swaps turning `page` into `target`: n minus the cycles of the permutation
+fn swaps(page: &[u8], target: &[u8]) -> usize {
+    let mut seen = vec![false; page.len()];
+    let mut cycles = 0;
+    for start in 0..page.len() {
+        if seen[start] {
+            continue;
+        }
+        cycles += 1;
+        let mut idx = start;
+        while !seen[idx] {
+            seen[idx] = true;
+            idx = target.iter().position(|p| *p == page[idx]).expect("same pages");
+        }
+    }
+    page.len() - cycles
+}
+
+fn part1(input: Input) -> Answer {
+    correct(&input).into()
 }
 
-fn part2(input: Input) -> i32 {
-    let (_correct, incorrect) = common(input);
-    incorrect
+fn part2(input: Input) -> Answer {
+    let (incorrect, _unordered) = reorder(&input);
+    incorrect.into()
 }
 
 
@@ -104,4 +169,42 @@ mod tests {
         let res = part2(input());
         assert_eq!(123, res)
     }
+
+    #[test]
+    fn test_ambiguous() {
+        assert!(ambiguous(&input()).is_empty());
+        let input = Input::from_str("1|2\n1|3\n\n1,2,3\n2,1\n").unwrap();
+        assert_eq!(vec![0], ambiguous(&input));
+    }
+
+    #[test]
+    fn test_partial_order() {
+        let input = Input::from_str("1|2\n1|3\n\n1,3,2\n3,1,2\n").unwrap();
+        assert_eq!(3, part1(input.clone()));
+        assert_eq!(2, part2(input));
+    }
+
+    #[test]
+    fn test_unordered() {
+        let input = Input::from_str("1|2\n\n1,2,1\n1,2\n").unwrap();
+        assert_eq!(2, part1(input.clone()));
+        assert_eq!((0, vec![(0, OrderError::Repeated(1))]), reorder(&input));
+
+        let input = Input::from_str("1|2\n2|1\n1|3\n\n1,3\n3,1\n2,1\n").unwrap();
+        assert_eq!(3, part1(input.clone()));
+        assert_eq!(3, part2(input.clone()));
+        assert_eq!(vec![2], reorder(&input).1.iter().map(|(idx, _)| *idx).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_explain() {
+        let explained = explain(&input());
+        assert_eq!(3, explained.len());
+        assert_eq!("update 3 breaks 97|75 (97 at 1, 75 at 0); moves 1, swaps 1", explained[0]);
+        assert_eq!(
+            "update 5 breaks 75|13 (75 at 2, 13 at 1), 29|13 (29 at 3, 13 at 1), \
+             47|13 (47 at 4, 13 at 1), 47|29 (47 at 4, 29 at 3); moves 2, swaps 2",
+            explained[2]
+        );
+    }
 }
@@ -1,6 +1,12 @@
+use crate::answer::Answer;
+use crate::cli;
+use crate::math::lcm;
+use crate::parse::ParseError;
 use crate::day06::Dir::*;
 use crate::day06::StepResult::{Edge, Step};
-use std::collections::HashSet;
+use crate::render;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::str::FromStr;
 
@@ -11,41 +17,80 @@ pub fn print() {
     println!("Day 6");
     println!("Part 1 {}", part1(input.clone()));
     println!("Part 2 {}", part2(input.clone()));
+
+    let walk = walk(&input.map, &input.guards[0], &[]);
+    if input.guards.len() > 1 {
+        for c in collisions(&input.map, &input.guards) {
+            let kind = if c.crossing { "cross" } else { "meet" };
+            println!("Guards {} and {} {} at {},{} after {} steps", c.guards.0, c.guards.1, kind, c.cell.0, c.cell.1, c.tick);
+        }
+        let (per_guard, combined) = coverage(&input.map, &input.guards);
+        println!("Coverage {:?}, {} combined", per_guard, combined);
+    }
+    for (option, goal) in [("--trap", Goal::Trap), ("--cover", Goal::Cover)] {
+        for budget in cli::options(option) {
+            let budget = budget.parse().expect("budget is a number");
+            match obstruct(&input.map, &input.guards[0], budget, goal) {
+                Some((placed, score)) => println!("{:?} with {:?}: {}", goal, placed, score),
+                None => println!("{:?} with {} obstructions: impossible", goal, budget),
+            }
+        }
+    }
+    if cli::flag("--path") {
+        print!("{}", to_string(&draw(&input.map, &walk.path, &[])));
+    }
+    for path in cli::options("--image") {
+        render::write_ppm(&path, &draw(&input.map, &walk.path, &[]), 4, colour).expect("cannot write image");
+    }
+    if cli::flag("--loops") {
+        for (obstruction, cycle) in loop_cycles(&input) {
+            println!("Obstruction at {},{} loops through {} steps", obstruction.0, obstruction.1, cycle.len());
+            print!("{}", to_string(&draw(&input.map, &cycle, &[obstruction])));
+        }
+    }
 }
 
 #[derive(Clone)]
 struct Input {
     map: Vec<Vec<char>>,
-    start: (usize, usize),
+    // in reading order, the puzzle has only the first one
+    guards: Vec<Pos>,
 }
 
-#[derive(Debug)]
-enum InputError {}
-
 impl FromStr for Input {
-    type Err = InputError;
+    type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let mut start = (0, 0);
+        let mut guards = vec![];
         let map = input.lines().enumerate()
             .map(|(x, line)| {
                 line.chars().enumerate().map(|(y, c)| {
-                    if c == '^' {
-                        start = (x, y)
+                    let dir = match c {
+                        '^' => Some(Up),
+                        '>' => Some(Right),
+                        'v' => Some(Down),
+                        '<' => Some(Left),
+                        _ => None,
+                    };
+                    if let Some(dir) = dir {
+                        guards.push(Pos { x, y, dir });
                     }
                     c
                 }).collect::<Vec<char>>()
             })
             .collect::<Vec<Vec<char>>>();
+        if guards.is_empty() {
+            return Err(ParseError::Malformed("no guard on the map".to_string()));
+        }
 
         Ok(Input {
             map,
-            start,
+            guards,
         })
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
 enum Dir {
     Up,
     Left,
@@ -53,6 +98,27 @@ enum Dir {
     Right,
 }
 
+impl Dir {
+    // clockwise from up, so turning right adds one
+    fn index(&self) -> usize {
+        match self {
+            Up => 0,
+            Right => 1,
+            Down => 2,
+            Left => 3,
+        }
+    }
+
+    fn delta(&self) -> (i64, i64) {
+        match self {
+            Up => (-1, 0),
+            Right => (0, 1),
+            Down => (1, 0),
+            Left => (0, -1),
+        }
+    }
+}
+
 enum StepResult {
     Edge,
     Step(usize, usize),
@@ -66,34 +132,8 @@ struct Pos {
 }
 
 
-fn part1(input: Input) -> i32 {
-    let mut map = input.map.clone();
-    let x_max = map.len() - 1;
-    let y_max = map[0].len() - 1;
-    let mut curr_pos = Pos {
-        x: input.start.0,
-        y: input.start.1,
-        dir: Up,
-    };
-
-    let mut num_visited = 1;
-    while let Step(x_next, y_next) = step(&curr_pos, x_max, y_max) {
-        if map[x_next][y_next] == '#' {
-            curr_pos = turn(&curr_pos);
-        } else {
-            if map[x_next][y_next] == '.' {
-                num_visited += 1;
-            }
-            map[curr_pos.x][curr_pos.y] = '*';
-            curr_pos = Pos {
-                x: x_next,
-                y: y_next,
-                dir: curr_pos.dir,
-            }
-        }
-    }
-
-    num_visited
+fn part1(input: Input) -> Answer {
+    route(&input.map, &input.guards[0]).len().into()
 }
 
 
@@ -128,174 +168,327 @@ fn turn(pos: &Pos) -> Pos {
 }
 
 
-fn loops(pos: &Pos, map: &Vec<Vec<char>>) -> bool {
-    let x_max = map.len() - 1;
-    let y_max = map[0].len() - 1;
-    let mut curr_pos = pos.clone();
-    let mut path: Vec<Pos> = vec![];
-    while let Step(x_next, y_next) = step(&curr_pos, x_max, y_max) {
-        if map[x_next][y_next] == '#' {
-            if path.iter().any(|p| p.eq(&curr_pos)) {
-                return true;
+/// Where the guard stops in front of the next obstacle, for every cell and direction,
+/// so a walk costs one lookup per turn instead of one per cell.
+struct JumpTable {
+    width: usize,
+    height: usize,
+    // indexed by `Dir::index`, then by cell; `None` if the guard walks off the map
+    next: [Vec<Option<usize>>; 4],
+}
+
+impl JumpTable {
+    fn new(map: &[Vec<char>]) -> Self {
+        let (height, width) = (map.len(), map[0].len());
+        let mut next: [Vec<Option<usize>>; 4] = std::array::from_fn(|_| vec![None; width * height]);
+        for dir in [Up, Right, Down, Left] {
+            // lines are walked against the direction of movement,
+            // so the last obstacle seen is the next one ahead
+            let lines: Vec<Vec<(usize, usize)>> = match dir {
+                Up => (0..width).map(|y| (0..height).map(|x| (x, y)).collect()).collect(),
+                Down => (0..width).map(|y| (0..height).rev().map(|x| (x, y)).collect()).collect(),
+                Left => (0..height).map(|x| (0..width).map(|y| (x, y)).collect()).collect(),
+                Right => (0..height).map(|x| (0..width).rev().map(|y| (x, y)).collect()).collect(),
+            };
+            for line in lines {
+                let (mut stop, mut blocked) = (None, false);
+                for (x, y) in line {
+                    if map[x][y] == '#' {
+                        blocked = true;
+                        continue;
+                    }
+                    if blocked {
+                        stop = Some(x * width + y);
+                        blocked = false;
+                    }
+                    next[dir.index()][x * width + y] = stop;
+                }
             }
-            let turned = turn(&curr_pos);
-            path.push(curr_pos);
-            curr_pos = turned;
-        } else {
-            curr_pos = Pos {
-                x: x_next,
-                y: y_next,
-                dir: curr_pos.dir,
+        }
+        JumpTable {
+            width,
+            height,
+            next,
+        }
+    }
+
+    /// Cell the guard stops at, still facing the obstacle, as if the `extra`
+    /// obstacles were also on the map. `None` if the guard leaves the map.
+    fn jump(&self, pos: &Pos, extra: &[(usize, usize)]) -> Option<Pos> {
+        let (dx, dy) = pos.dir.delta();
+        // number of steps from `pos` to `(x, y)`, if it's straight ahead
+        let ahead = |(x, y): (usize, usize)| {
+            let (ax, ay) = (x as i64 - pos.x as i64, y as i64 - pos.y as i64);
+            let dist = ax * dx + ay * dy;
+            (dist > 0 && ax == dx * dist && ay == dy * dist).then_some(dist)
+        };
+        let stop = self.next[pos.dir.index()][pos.x * self.width + pos.y]
+            .map(|cell| (cell / self.width, cell % self.width));
+        let limit = stop.map_or(i64::MAX, |cell| ahead(cell).unwrap_or(0));
+        let steps = extra.iter().filter_map(|&e| ahead(e)).filter(|&d| d <= limit).min();
+        match steps {
+            Some(d) => Some(Pos {
+                x: (pos.x as i64 + dx * (d - 1)) as usize,
+                y: (pos.y as i64 + dy * (d - 1)) as usize,
+                dir: pos.dir,
+            }),
+            None => stop.map(|(x, y)| Pos { x, y, dir: pos.dir }),
+        }
+    }
+
+    /// Jumps from turn to turn until the guard leaves or repeats a (cell, direction).
+    fn loops(&self, start: &Pos, extra: &[(usize, usize)]) -> bool {
+        let mut seen = BitSet::new(self.width * self.height * 4);
+        let mut pos = start.clone();
+        while let Some(stop) = self.jump(&pos, extra) {
+            if !seen.insert((stop.x * self.width + stop.y) * 4 + stop.dir.index()) {
+                return true;
             }
+            pos = turn(&stop);
         }
+        false
     }
-    false
 }
-fn part2(input: Input) -> i32 {
-    let mut map = input.map.clone();
-    let x_max = map.len() - 1;
-    let y_max = map[0].len() - 1;
-    let mut curr_pos = Pos {
-        x: input.start.0,
-        y: input.start.1,
-        dir: Up,
-    };
 
-    let mut new_stones = HashSet::new();
-    while let Step(x_next, y_next) = step(&curr_pos, x_max, y_max) {
-        if map[x_next][y_next] == '#' {
-            curr_pos = turn(&curr_pos);
-        } else {
-            if map[x_next][y_next] == '.' {
-                map[x_next][y_next] = '#';
-                if loops(&curr_pos, &map) {
-                    new_stones.insert((x_next, y_next));
+struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    fn new(len: usize) -> Self {
+        BitSet {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    /// Sets the bit, returns false if it was already set.
+    fn insert(&mut self, idx: usize) -> bool {
+        let (word, bit) = (idx / 64, 1 << (idx % 64));
+        let new = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        new
+    }
+}
+
+// distinct cells the guard walks through, in order
+fn route(map: &[Vec<char>], start: &Pos) -> Vec<(usize, usize)> {
+    let mut seen = HashSet::new();
+    walk(map, start, &[]).path.iter()
+        .map(|pos| (pos.x, pos.y))
+        .filter(|&cell| seen.insert(cell))
+        .collect()
+}
+
+// an obstruction only changes anything on the guard's route, and can't go on the start
+fn part2(input: Input) -> Answer {
+    let start = input.guards[0].clone();
+    let table = JumpTable::new(&input.map);
+    let candidates = route(&input.map, &start);
+
+    candidates[1..].par_iter()
+        .filter(|&&cell| table.loops(&start, &[cell]))
+        .count()
+        .into()
+}
+
+
+/// States the guard goes through, `cycle` is where the repeating part starts if it loops.
+struct Walk {
+    path: Vec<Pos>,
+    cycle: Option<usize>,
+}
+
+/// Cell by cell walk with `extra` obstacles, every turn is a separate state on the same cell.
+fn walk(map: &[Vec<char>], start: &Pos, extra: &[(usize, usize)]) -> Walk {
+    let (x_max, y_max) = (map.len() - 1, map[0].len() - 1);
+    let mut seen: HashMap<Pos, usize> = HashMap::new();
+    let mut path = vec![];
+    let mut curr_pos = start.clone();
+    loop {
+        if let Some(&idx) = seen.get(&curr_pos) {
+            return Walk {
+                path,
+                cycle: Some(idx),
+            };
+        }
+        seen.insert(curr_pos.clone(), path.len());
+        path.push(curr_pos.clone());
+        curr_pos = match step(&curr_pos, x_max, y_max) {
+            Edge => return Walk {
+                path,
+                cycle: None,
+            },
+            Step(x, y) if map[x][y] == '#' || extra.contains(&(x, y)) => turn(&curr_pos),
+            Step(x, y) => Pos { x, y, dir: curr_pos.dir },
+        };
+    }
+}
+
+/// Every obstruction that makes the guard loop, with the states of the loop.
+fn loop_cycles(input: &Input) -> Vec<((usize, usize), Vec<Pos>)> {
+    let start = input.guards[0].clone();
+    let table = JumpTable::new(&input.map);
+    route(&input.map, &start)[1..].iter()
+        .filter(|&&cell| table.loops(&start, &[cell]))
+        .map(|&cell| {
+            let Walk { mut path, cycle } = walk(&input.map, &start, &[cell]);
+            (cell, path.split_off(cycle.expect("obstruction makes a loop")))
+        })
+        .collect()
+}
+
+/// The map in the puzzle's notation: `|` and `-` for vertical and horizontal
+/// moves, `+` where both cross or the guard turns, `O` for the obstructions.
+fn draw(map: &[Vec<char>], path: &[Pos], obstructions: &[(usize, usize)]) -> Vec<Vec<char>> {
+    let mut grid = map.to_vec();
+    for pos in path {
+        let cell = &mut grid[pos.x][pos.y];
+        let vertical = matches!(pos.dir, Up | Down);
+        *cell = match (*cell, vertical) {
+            ('^' | '>' | 'v' | '<', _) => *cell,
+            ('.', true) | ('|', true) => '|',
+            ('.', false) | ('-', false) => '-',
+            _ => '+',
+        };
+    }
+    obstructions.iter().for_each(|&(x, y)| grid[x][y] = 'O');
+    grid
+}
+
+fn to_string(grid: &[Vec<char>]) -> String {
+    grid.iter().map(|row| row.iter().collect::<String>() + "\n").collect()
+}
+
+fn colour(c: char) -> [u8; 3] {
+    match c {
+        '#' => [90, 90, 90],
+        'O' => [220, 40, 40],
+        '|' | '-' | '+' => [240, 200, 60],
+        '.' => [20, 20, 30],
+        _ => [80, 160, 255],
+    }
+}
+
+/// Where a guard is after `tick` moves or turns: on its walk, then around
+/// its loop forever, or nowhere once it has left the map.
+fn state_at(walk: &Walk, tick: usize) -> Option<&Pos> {
+    match walk.cycle {
+        Some(start) if tick >= walk.path.len() => {
+            Some(&walk.path[start + (tick - start) % (walk.path.len() - start)])
+        }
+        _ => walk.path.get(tick),
+    }
+}
+
+/// Two guards on the same cell after `tick`, or swapping cells during it.
+#[derive(Debug, Eq, PartialEq)]
+struct Collision {
+    tick: usize,
+    guards: (usize, usize),
+    cell: (usize, usize),
+    crossing: bool,
+}
+
+/// Guards move at the same time without blocking each other. Once both of a pair
+/// are on their loops the pair repeats, so only the first period is reported.
+fn collisions(map: &[Vec<char>], guards: &[Pos]) -> Vec<Collision> {
+    let walks = guards.iter().map(|g| walk(map, g, &[])).collect::<Vec<_>>();
+    let cell = |walk: &Walk, tick: usize| state_at(walk, tick).map(|p| (p.x, p.y));
+    let mut found = vec![];
+    for a in 0..walks.len() {
+        for b in a + 1..walks.len() {
+            let (wa, wb) = (&walks[a], &walks[b]);
+            let horizon = match (wa.cycle, wb.cycle) {
+                (Some(sa), Some(sb)) => {
+                    let period = lcm((wa.path.len() - sa) as i64, (wb.path.len() - sb) as i64) as usize;
+                    sa.max(sb) + period
+                }
+                // a looping guard stays on the map, so only the one leaving bounds the scan
+                (Some(_), None) => wb.path.len(),
+                (None, Some(_)) => wa.path.len(),
+                (None, None) => wa.path.len().min(wb.path.len()),
+            };
+            for tick in 0..horizon {
+                let (Some(ca), Some(cb)) = (cell(wa, tick), cell(wb, tick)) else {
+                    continue;
+                };
+                let crossing = tick > 0 && Some(ca) == cell(wb, tick - 1) && Some(cb) == cell(wa, tick - 1);
+                if ca == cb || crossing {
+                    found.push(Collision { tick, guards: (a, b), cell: ca, crossing });
                 }
-                map[x_next][y_next] = '.';
-            }
-            map[curr_pos.x][curr_pos.y] = '*';
-            curr_pos = Pos {
-                x: x_next,
-                y: y_next,
-                dir: curr_pos.dir,
             }
         }
     }
+    found.sort_by_key(|c| (c.tick, c.guards));
+    found
+}
+
+/// Distinct cells visited by each guard, and by all of them together.
+fn coverage(map: &[Vec<char>], guards: &[Pos]) -> (Vec<usize>, usize) {
+    let routes = guards.iter().map(|g| route(map, g)).collect::<Vec<_>>();
+    let combined = routes.iter().flatten().collect::<HashSet<_>>().len();
+    (routes.iter().map(|r| r.len()).collect(), combined)
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Goal {
+    /// Make the guard repeat a state after as few moves and turns as possible.
+    Trap,
+    /// Let the guard leave after visiting as few cells as possible.
+    Cover,
+}
+
+fn score(walk: &Walk, goal: Goal) -> Option<usize> {
+    match (goal, walk.cycle) {
+        (Goal::Trap, Some(_)) => Some(walk.path.len()),
+        (Goal::Cover, None) => Some(walk.path.iter().map(|p| (p.x, p.y)).collect::<HashSet<_>>().len()),
+        _ => None,
+    }
+}
 
-    new_stones.len() as i32
+/// Best placement of at most `budget` obstructions for `goal`, with its score.
+fn obstruct(map: &[Vec<char>], start: &Pos, budget: usize, goal: Goal) -> Option<(Vec<(usize, usize)>, usize)> {
+    let mut best = None;
+    search(map, start, budget, goal, &mut vec![], &mut best);
+    best
 }
-//
-// fn print_stones(map: &Vec<Vec<char>>, stones: &HashSet<(usize, usize)>) {
-//     let mut curr_map = map.clone();
-//     stones.iter().for_each(|(x_next, y_next)| {
-//         curr_map[*x_next][*y_next] = 'O';
-//     });
-//     println!("New Path");
-//     curr_map.iter().for_each(|row| {
-//         row.iter().for_each(|c| {
-//             print!("{}", c);
-//         });
-//         println!();
-//     });
-// }
-//
-//
-// fn next_stone(pos: &Pos,
-//               stones: &Vec<(usize, usize)>) -> Option<Pos> {
-//     let Pos { x, y, dir } = pos;
-//     match dir {
-//         Up => {
-//             stones.iter().filter(|(stone_x, stone_y)| {
-//                 *stone_x < *x && *stone_y == *y
-//             }).max_by_key(|(x, _)| x).copied()
-//                 .map(|(x, y)| Pos { x: x + 1, y: y, dir: dir.clone() })
-//         }
-//         Left => {
-//             stones.iter().filter(|(stone_x, stone_y)| {
-//                 *stone_x == *x && *stone_y < *y
-//             }).max_by_key(|(_, y)| y).copied()
-//                 .map(|(x, y)| Pos { x: x, y: y + 1, dir: dir.clone() })
-//         }
-//         Right => {
-//             stones.iter().filter(|(stone_x, stone_y)| {
-//                 *stone_x == *x && *stone_y > *y
-//             }).min_by_key(|(_, y)| y).copied()
-//                 .map(|(x, y)| Pos { x: x, y: y - 1, dir: dir.clone() })
-//         }
-//         Down => {
-//             stones.iter().filter(|(stone_x, stone_y)| {
-//                 *stone_x > *x && *stone_y == *y
-//             }).min_by_key(|(x, _)| x).copied()
-//                 .map(|(x, y)| Pos { x: x - 1, y: y, dir: dir.clone() })
-//         }
-//     }
-// }
-//
-// fn check_loop(start: &Pos, stones: &Vec<(usize, usize)>) -> Vec<Pos> {
-//     let mut next = start.clone();
-//     let mut path: Vec<Pos> = vec![];
-//     while let Some(stone) = next_stone(&next, stones) {
-//         if path.iter().any(|p| p.eq(&stone)) {
-//             return path;
-//         }
-//         next = turn(&stone);
-//         path.push(stone);
-//     }
-//     vec![]
-// }
-// // Actually slower
-// fn part2_skip_stones(input: Input) -> i32 {
-//     let mut map = input.map.clone();
-//     let x_max = map.len() - 1;
-//     let y_max = map[0].len() - 1;
-//
-//     let mut stones = map.iter().enumerate().flat_map(|(x, row)| {
-//         let result = row.iter().enumerate()
-//             .filter(|(_, &c)| c == '#')
-//             .map(|(y, _)| y)
-//             .map(move |y| {
-//                 (x, y)
-//             });
-//         result
-//     }).collect::<Vec<(usize, usize)>>();
-//
-//
-//     let mut curr_pos = Pos {
-//         x: input.start.0,
-//         y: input.start.1,
-//         dir: Up,
-//     };
-//     let mut new_stones = HashSet::new();
-//     while let Step(x_next, y_next) = step(&curr_pos, x_max, y_max) {
-//         let turned = turn(&curr_pos);
-//         if map[x_next][y_next] == '#' {
-//             curr_pos = turned;
-//         } else {
-//             if map[x_next][y_next] == '.' {
-//                 if let Some(pos) = next_stone(&turned, &stones) {
-//                     let stone = (x_next, y_next);
-//                     if !new_stones.contains(&stone) && !stone.eq(&input.start) {
-//                         stones.push(stone);
-//                         let path = check_loop(&turned, &stones);
-//                         let stone = stones.pop().unwrap();
-//                         if !path.is_empty() {
-//                             new_stones.insert(stone);
-//                         }
-//                     }
-//                 }
-//             }
-//             map[curr_pos.x][curr_pos.y] = '*';
-//
-//             curr_pos = Pos {
-//                 x: x_next,
-//                 y: y_next,
-//                 dir: curr_pos.dir,
-//             }
-//         }
-//     }
-//     new_stones.len() as i32
-// }
 
+// Branch and bound over the cells the guard is about to step into. Up to that step
+// the walk doesn't change, so once its prefix is as bad as the best, so is the rest.
+fn search(
+    map: &[Vec<char>],
+    start: &Pos,
+    budget: usize,
+    goal: Goal,
+    placed: &mut Vec<(usize, usize)>,
+    best: &mut Option<(Vec<(usize, usize)>, usize)>,
+) {
+    let walk = walk(map, start, placed);
+    if let Some(score) = score(&walk, goal) {
+        if best.as_ref().is_none_or(|(_, b)| score < *b) {
+            *best = Some((placed.clone(), score));
+        }
+    }
+    if budget == 0 {
+        return;
+    }
+    let mut visited = HashSet::from([(start.x, start.y)]);
+    for idx in 1..walk.path.len() {
+        let cell = (walk.path[idx].x, walk.path[idx].y);
+        let bound = match goal {
+            Goal::Trap => idx + 1,
+            Goal::Cover => visited.len(),
+        };
+        if best.as_ref().is_some_and(|(_, b)| bound >= *b) {
+            return;
+        }
+        if visited.insert(cell) {
+            placed.push(cell);
+            search(map, start, budget - 1, goal, placed, best);
+            placed.pop();
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -350,4 +543,98 @@ mod tests {
         let num_stones = part2(input);
         assert_eq!(5, num_stones)
     }
+
+    #[test]
+    fn test_jump_table() {
+        let input = input();
+        let table = JumpTable::new(&input.map);
+        let start = Pos { x: 6, y: 4, dir: Up };
+        assert_eq!(Some(Pos { x: 1, y: 4, dir: Up }), table.jump(&start, &[]));
+        assert_eq!(Some(Pos { x: 4, y: 4, dir: Up }), table.jump(&start, &[(3, 4), (2, 4)]));
+        assert_eq!(Some(Pos { x: 1, y: 4, dir: Up }), table.jump(&start, &[(6, 3), (7, 4)]));
+        assert_eq!(None, table.jump(&Pos { x: 9, y: 9, dir: Right }, &[]));
+        assert!(table.loops(&start, &[(6, 3)]));
+        assert!(!table.loops(&start, &[(1, 1)]));
+    }
+
+    #[test]
+    fn test_draw() {
+        let input = input();
+        let start = Pos { x: 6, y: 4, dir: Up };
+        let path = walk(&input.map, &start, &[]).path;
+        assert_eq!(Pos { x: 1, y: 4, dir: Right }, path[6]);
+        assert_eq!(Pos { x: 9, y: 7, dir: Down }, path[path.len() - 1]);
+
+        let cycles = loop_cycles(&input);
+        assert_eq!(6, cycles.len());
+        let (obstruction, cycle) = &cycles[0];
+        assert_eq!((6, 3), *obstruction);
+        assert_eq!(Pos { x: 6, y: 4, dir: Up }, cycle[0]);
+        let expected = "\
+....#.....
+....+---+#
+....|...|.
+..#.|...|.
+....|..#|.
+....|...|.
+.#.O^---+.
+........#.
+#.........
+......#...
+";
+        assert_eq!(expected, to_string(&draw(&input.map, cycle, &[(6, 3)])));
+    }
+
+    #[test]
+    fn test_guards() {
+        let input = Input::from_str(".....\n.>.<.\n..v..\n").unwrap();
+        assert_eq!(vec![
+            Pos { x: 1, y: 1, dir: Right },
+            Pos { x: 1, y: 3, dir: Left },
+            Pos { x: 2, y: 2, dir: Down },
+        ], input.guards);
+        assert_eq!(vec![
+            Collision { tick: 1, guards: (0, 1), cell: (1, 2), crossing: false },
+        ], collisions(&input.map, &input.guards));
+        assert_eq!((vec![4, 4, 1], 6), coverage(&input.map, &input.guards));
+
+        let input = Input::from_str(".><.\n").unwrap();
+        assert_eq!(vec![
+            Collision { tick: 1, guards: (0, 1), cell: (0, 2), crossing: true },
+        ], collisions(&input.map, &input.guards));
+        assert!(Input::from_str("..#\n").is_err());
+
+        // guard 0 loops every 20 steps, guard 1 meets it on the way out after 33
+        let mut map = vec![vec!['.'; 7]; 39];
+        for (x, y) in [(0, 1), (1, 6), (6, 5), (5, 0)] {
+            map[x][y] = '#';
+        }
+        map[1][1] = '^';
+        map[38][3] = '^';
+        let text = map.iter().map(|row| row.iter().collect::<String>() + "\n").collect::<String>();
+        let input = Input::from_str(&text).unwrap();
+        assert_eq!(vec![
+            Collision { tick: 33, guards: (0, 1), cell: (5, 3), crossing: false },
+        ], collisions(&input.map, &input.guards));
+    }
+
+    #[test]
+    fn test_obstruct() {
+        let input = input();
+        let start = &input.guards[0];
+        let cells = (0..10).flat_map(|x| (0..10).map(move |y| (x, y)))
+            .filter(|&(x, y)| input.map[x][y] == '.')
+            .collect::<Vec<_>>();
+        for goal in [Goal::Trap, Goal::Cover] {
+            let single = cells.iter().filter_map(|&c| score(&walk(&input.map, start, &[c]), goal)).min();
+            let pairs = cells.iter().enumerate()
+                .flat_map(|(i, &a)| cells[i + 1..].iter().map(move |&b| [a, b]))
+                .filter_map(|pair| score(&walk(&input.map, start, &pair), goal))
+                .chain(single)
+                .min();
+            assert_eq!(single, obstruct(&input.map, start, 1, goal).map(|(_, s)| s));
+            assert_eq!(pairs, obstruct(&input.map, start, 2, goal).map(|(_, s)| s));
+        }
+        assert_eq!(Some((vec![], 41)), obstruct(&input.map, start, 0, Goal::Cover));
+    }
 }
@@ -1,3 +1,9 @@
+use crate::answer::Answer;
+use crate::cli;
+use crate::parse::ParseError;
+use num_bigint::BigInt;
+use std::cell::Cell;
+use std::fmt::{Display, Formatter};
 use std::fs;
 use std::str::FromStr;
 
@@ -8,6 +14,23 @@ pub fn print() {
     println!("Day 7");
     println!("Part 1 {}", part1(input.clone()));
     println!("Part 2 {}", part2(input.clone()));
+
+    for spec in cli::options("--ops") {
+        match calibrate(&input, &spec) {
+            Ok(sum) => println!("Calibration with {} {}", spec, sum),
+            Err(err) => println!("Calibration with {} fails, {}", spec, err),
+        }
+    }
+    if cli::flag("--report") {
+        let spec = cli::options("--ops").pop().unwrap_or("+,*,||".to_string());
+        let all = cli::flag("--all");
+        let report = if cli::flag("--big") {
+            report(&input, &operators::<BigInt>(&spec).expect("unknown operator"), all)
+        } else {
+            report(&input, &operators::<i128>(&spec).expect("unknown operator"), all)
+        };
+        report.iter().for_each(|line| println!("{}", line));
+    }
 }
 
 #[derive(Clone)]
@@ -15,10 +38,11 @@ struct Input {
     expressions: Vec<Expression>,
 }
 
+// kept exact, the solver converts into the type it runs in
 #[derive(Clone)]
 struct Expression {
-    expected: i64,
-    values: Vec<i64>,
+    expected: BigInt,
+    values: Vec<BigInt>,
     sizes: Vec<usize>,
 }
 
@@ -31,11 +55,11 @@ impl FromStr for Input {
     fn from_str(input: &str) -> Result<Self, Self::Err> {
         let expressions = input.lines().map(|l| {
             let parts = l.splitn(2, ": ").collect::<Vec<&str>>();
-            let expected = i64::from_str(parts[0]).expect("cannot parse expected value");
+            let expected = BigInt::from_str(parts[0]).expect("cannot parse expected value");
             let strings = parts[1].split(" ").collect::<Vec<&str>>();
             let values = strings.iter().map(|v| {
-                i64::from_str(v).expect("cannot parse value")
-            }).collect::<Vec<i64>>();
+                BigInt::from_str(v).expect("cannot parse value")
+            }).collect::<Vec<BigInt>>();
             let sizes = strings.iter().map(|v| v.len()).collect();
             Expression {
                 expected,
@@ -50,329 +74,555 @@ impl FromStr for Input {
 }
 
 
-fn part1(input: Input) -> i64 {
-    input.expressions.iter().fold(0, |acc, expr: &Expression| {
-        acc + part1_queue_reversed(expr.expected, &expr.values)
-    })
+fn part1(input: Input) -> Answer {
+    calibrate(&input, "+,*").expect("adding and multiplying never hits the power cap")
+}
+
+fn part2(input: Input) -> Answer {
+    calibrate(&input, "+,*,||").expect("adding, multiplying and concatenating never hits the power cap")
+}
+
+// checked i128 first, big integers only if that overflows; those only fail
+// when a power grows beyond `MAX_BITS`
+fn calibrate(input: &Input, spec: &str) -> Result<Answer, Overflow> {
+    match calibration(input, &operators::<i128>(spec).expect("unknown operator")) {
+        Ok(sum) => Ok(sum.into()),
+        Err(_) => calibration(input, &operators::<BigInt>(spec).expect("unknown operator")).map(Answer::from),
+    }
+}
+
+fn calibration<N: Num>(input: &Input, ops: &[&'static dyn Operator<N>]) -> Result<N, Overflow> {
+    let mut sum = N::zero();
+    for expr in &input.expressions {
+        if !witnesses(expr, ops, 1)?.is_empty() {
+            let expected = N::from_big(&expr.expected).ok_or(Overflow::new(expr))?;
+            sum = sum.checked_add(&expected).ok_or(Overflow::new(expr))?;
+        }
+    }
+    Ok(sum)
+}
+
+/// An equation that couldn't be decided because a value didn't fit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Overflow {
+    expected: String,
 }
 
-fn part1_queue_reversed(expected: i64, values: &[i64]) -> i64 {
-    let mut queue: Vec<Pos> = Vec::new();
-    let last = values.len();
-    let start = Pos::new(expected, last);
-    queue.push(start);
-    while let Some(element) = queue.pop() {
-        if element.idx > 0 {
-            let next_idx = element.idx - 1;
-            let next = values[next_idx];
+impl Overflow {
+    fn new(expr: &Expression) -> Self {
+        Overflow {
+            expected: expr.expected.to_string(),
+        }
+    }
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "equation {} overflows", self.expected)
+    }
+}
 
-            // can be divided?
-            if let Some(pos) = element.div(next) {
-                if pos.value == 1 && next_idx == 0 {
-                    return expected;
-                } else {
-                    queue.push(pos);
+/// Integer type the solver runs in. Every operation is checked and gives `None`
+/// if the result doesn't fit, so a value never silently wraps around.
+trait Num: Clone + Ord + Display + 'static {
+    fn from_big(value: &BigInt) -> Option<Self>;
+    fn zero() -> Self;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    fn checked_div(&self, other: &Self) -> Option<Self>;
+    fn checked_rem(&self, other: &Self) -> Option<Self>;
+    fn checked_pow(&self, exp: u32) -> Option<Self>;
+    fn xor(&self, other: &Self) -> Self;
+    fn to_u32(&self) -> Option<u32>;
+}
+
+macro_rules! checked_num {
+    ($($t:ty),*) => {
+        $(
+            impl Num for $t {
+                fn from_big(value: &BigInt) -> Option<Self> {
+                    <$t>::try_from(value).ok()
+                }
+
+                fn zero() -> Self {
+                    0
+                }
+
+                fn checked_add(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *other)
+                }
+
+                fn checked_sub(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_sub(*self, *other)
+                }
+
+                fn checked_mul(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_mul(*self, *other)
                 }
-            }
 
-            // can subtract?
-            if let Some(pos) = element.sub(next) {
-                if pos.value > 0 {
-                    queue.push(pos);
-                } else if pos.value == 1 && next_idx == 0 {
-                    return expected;
+                fn checked_div(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_div(*self, *other)
+                }
+
+                fn checked_rem(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_rem(*self, *other)
+                }
+
+                fn checked_pow(&self, exp: u32) -> Option<Self> {
+                    <$t>::checked_pow(*self, exp)
+                }
+
+                fn xor(&self, other: &Self) -> Self {
+                    self ^ other
+                }
+
+                fn to_u32(&self) -> Option<u32> {
+                    u32::try_from(*self).ok()
                 }
             }
+        )*
+    };
+}
+
+checked_num!(i64, i128);
+
+// big integers only run out of memory, powers are capped well before that
+const MAX_BITS: u64 = 1 << 20;
+
+impl Num for BigInt {
+    fn from_big(value: &BigInt) -> Option<Self> {
+        Some(value.clone())
+    }
+
+    fn zero() -> Self {
+        BigInt::from(0)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        Some(self - other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn checked_div(&self, other: &Self) -> Option<Self> {
+        (*other != BigInt::zero()).then(|| self / other)
+    }
+
+    fn checked_rem(&self, other: &Self) -> Option<Self> {
+        (*other != BigInt::zero()).then(|| self % other)
+    }
+
+    fn checked_pow(&self, exp: u32) -> Option<Self> {
+        (self.bits().saturating_mul(exp as u64) <= MAX_BITS).then(|| self.pow(exp))
+    }
+
+    fn xor(&self, other: &Self) -> Self {
+        self ^ other
+    }
+
+    fn to_u32(&self) -> Option<u32> {
+        u32::try_from(self).ok()
+    }
+}
+
+enum Outcome<N> {
+    Value(N),
+    Undefined,
+    Overflow,
+}
+
+impl<N> From<Option<N>> for Outcome<N> {
+    fn from(value: Option<N>) -> Self {
+        value.map_or(Outcome::Overflow, Outcome::Value)
+    }
+}
+
+/// Left operand that gives a result with a right operand.
+enum Inverse<N> {
+    Left(N),
+    Impossible,
+    /// Several or unknown left operands, the solver has to evaluate them forward.
+    Forward,
+    Overflow,
+}
+
+impl<N> From<Option<N>> for Inverse<N> {
+    fn from(value: Option<N>) -> Self {
+        value.map_or(Inverse::Overflow, Inverse::Left)
+    }
+}
+
+trait Operator<N> {
+    fn symbol(&self) -> &'static str;
+
+    /// `size` is the number of digits `right` was written with.
+    fn apply(&self, left: &N, right: &N, size: usize) -> Outcome<N>;
+
+    fn invert(&self, _target: &N, _right: &N, _size: usize) -> Inverse<N> {
+        Inverse::Forward
+    }
+
+    /// Whether positive operands always give a positive result. If every operator
+    /// does, the reverse search can drop any left operand below one.
+    fn keeps_positive(&self) -> bool {
+        false
+    }
+}
+
+struct Add;
+struct Mul;
+struct Concat;
+struct Sub;
+struct Pow;
+struct Max;
+struct Xor;
+
+impl<N: Num> Operator<N> for Add {
+    fn symbol(&self) -> &'static str {
+        "+"
+    }
+
+    fn apply(&self, left: &N, right: &N, _size: usize) -> Outcome<N> {
+        left.checked_add(right).into()
+    }
+
+    fn invert(&self, target: &N, right: &N, _size: usize) -> Inverse<N> {
+        target.checked_sub(right).into()
+    }
+
+    fn keeps_positive(&self) -> bool {
+        true
+    }
+}
+
+impl<N: Num> Operator<N> for Mul {
+    fn symbol(&self) -> &'static str {
+        "*"
+    }
+
+    fn apply(&self, left: &N, right: &N, _size: usize) -> Outcome<N> {
+        left.checked_mul(right).into()
+    }
+
+    fn invert(&self, target: &N, right: &N, _size: usize) -> Inverse<N> {
+        let zero = N::zero();
+        if *right == zero {
+            return if *target == zero { Inverse::Forward } else { Inverse::Impossible };
+        }
+        match target.checked_rem(right) {
+            Some(rem) if rem == zero => target.checked_div(right).into(),
+            Some(_) => Inverse::Impossible,
+            None => Inverse::Overflow,
         }
     }
-    0
+
+    fn keeps_positive(&self) -> bool {
+        true
+    }
+}
+
+fn scale<N: Num>(size: usize) -> Option<N> {
+    N::from_big(&BigInt::from(10))?.checked_pow(u32::try_from(size).ok()?)
 }
 
+impl<N: Num> Operator<N> for Concat {
+    fn symbol(&self) -> &'static str {
+        "||"
+    }
 
-#[derive(Eq, PartialEq)]
-struct Pos {
-    value: i64,
-    idx: usize,
+    fn apply(&self, left: &N, right: &N, size: usize) -> Outcome<N> {
+        scale::<N>(size).and_then(|scale| scale.checked_mul(left)?.checked_add(right)).into()
+    }
+
+    fn invert(&self, target: &N, right: &N, size: usize) -> Inverse<N> {
+        let (Some(scale), Some(rest)) = (scale::<N>(size), target.checked_sub(right)) else {
+            return Inverse::Overflow;
+        };
+        match rest.checked_rem(&scale) {
+            Some(rem) if rem == N::zero() => rest.checked_div(&scale).into(),
+            _ => Inverse::Impossible,
+        }
+    }
+
+    fn keeps_positive(&self) -> bool {
+        true
+    }
 }
-impl Pos {
-    fn new(value: i64, idx: usize) -> Self {
-        Pos { value, idx }
+
+impl<N: Num> Operator<N> for Sub {
+    fn symbol(&self) -> &'static str {
+        "-"
     }
 
-    fn div(&self, other: i64) -> Option<Self> {
-        if self.value % other == 0 {
-            Some(
-                Pos {
-                    value: self.value / other,
-                    idx: self.idx - 1,
-                }
-            )
-        } else {
-            None
+    fn apply(&self, left: &N, right: &N, _size: usize) -> Outcome<N> {
+        left.checked_sub(right).into()
+    }
+
+    fn invert(&self, target: &N, right: &N, _size: usize) -> Inverse<N> {
+        target.checked_add(right).into()
+    }
+}
+
+// no inverse: integer roots are left to the forward search
+impl<N: Num> Operator<N> for Pow {
+    fn symbol(&self) -> &'static str {
+        "^"
+    }
+
+    fn apply(&self, left: &N, right: &N, _size: usize) -> Outcome<N> {
+        if *right < N::zero() {
+            return Outcome::Undefined;
         }
+        right.to_u32().and_then(|exp| left.checked_pow(exp)).into()
     }
 
-    fn sub(&self, other: i64) -> Option<Self> {
-        Some(
-            Pos {
-                value: self.value - other,
-                idx: self.idx - 1,
-            }
-        )
+    fn keeps_positive(&self) -> bool {
+        true
     }
+}
 
-    fn drop(&self, other: i64, size: usize) -> Option<Self> {
-        let scale = i64::pow(10, size as u32);
+impl<N: Num> Operator<N> for Max {
+    fn symbol(&self) -> &'static str {
+        "max"
+    }
 
-        if self.value % scale == other {
-            let val = (self.value - other) / scale;
-            Some(Pos {
-                value: val,
-                idx: self.idx - 1,
-            })
-        } else {
-            None
+    fn apply(&self, left: &N, right: &N, _size: usize) -> Outcome<N> {
+        Outcome::Value(left.clone().max(right.clone()))
+    }
+
+    // any left operand up to `right` gives `right`
+    fn invert(&self, target: &N, right: &N, _size: usize) -> Inverse<N> {
+        match target.cmp(right) {
+            std::cmp::Ordering::Greater => Inverse::Left(target.clone()),
+            std::cmp::Ordering::Equal => Inverse::Forward,
+            std::cmp::Ordering::Less => Inverse::Impossible,
         }
     }
+
+    fn keeps_positive(&self) -> bool {
+        true
+    }
+}
+
+impl<N: Num> Operator<N> for Xor {
+    fn symbol(&self) -> &'static str {
+        "xor"
+    }
+
+    fn apply(&self, left: &N, right: &N, _size: usize) -> Outcome<N> {
+        Outcome::Value(left.xor(right))
+    }
+
+    fn invert(&self, target: &N, right: &N, _size: usize) -> Inverse<N> {
+        Inverse::Left(target.xor(right))
+    }
 }
 
-fn part2(input: Input) -> i64 {
-    input.expressions.iter().fold(0, |acc, expr: &Expression| {
-        acc + part2_queue_reversed(expr.expected, &expr.values, &expr.sizes)
-    })
+/// Operators by symbol from a comma separated list, e.g. `+,*,||`.
+fn operators<N: Num>(spec: &str) -> Result<Vec<&'static dyn Operator<N>>, ParseError> {
+    spec.split(',').map(|symbol| -> Result<&'static dyn Operator<N>, ParseError> {
+        Ok(match symbol.trim() {
+            "+" => &Add,
+            "*" => &Mul,
+            "||" => &Concat,
+            "-" => &Sub,
+            "^" => &Pow,
+            "max" => &Max,
+            "xor" => &Xor,
+            _ => return Err(ParseError::Malformed(symbol.to_string())),
+        })
+    }).collect()
 }
 
+type Ops<N> = Vec<&'static dyn Operator<N>>;
 
-fn part2_queue_reversed(expected: i64, values: &[i64], sizes: &[usize]) -> i64 {
-    let mut queue: Vec<Pos> = Vec::new();
-    let last = values.len();
-    let start = Pos::new(expected, last);
-    queue.push(start);
-    while let Some(element) = queue.pop() {
-        if element.idx > 0 {
-            let next_idx = element.idx - 1;
-            let size = sizes[next_idx];
-            let next = values[next_idx];
+struct Search<'a, N: Num> {
+    target: N,
+    values: Vec<N>,
+    sizes: &'a [usize],
+    allowed: &'a [&'static dyn Operator<N>],
+    positive: bool,
+    overflow: Cell<bool>,
+}
 
-            // can be divided?
-            if let Some(pos) = element.div(next) {
-                if pos.value == 1 && next_idx == 0 {
-                    return expected;
-                } else {
-                    queue.push(pos);
-                }
-            }
+impl<'a, N: Num> Search<'a, N> {
+    fn new(expr: &'a Expression, allowed: &'a [&'static dyn Operator<N>]) -> Result<Self, Overflow> {
+        let target = N::from_big(&expr.expected).ok_or(Overflow::new(expr))?;
+        let values = expr.values.iter().map(N::from_big).collect::<Option<Vec<_>>>().ok_or(Overflow::new(expr))?;
+        let positive = allowed.iter().all(|op| op.keeps_positive()) && values.iter().all(|v| *v > N::zero());
+        Ok(Search {
+            target,
+            values,
+            sizes: &expr.sizes,
+            allowed,
+            positive,
+            overflow: Cell::new(false),
+        })
+    }
+
+    fn run(&self, found: &mut dyn FnMut(&[&'static dyn Operator<N>]) -> bool) {
+        self.reverse(self.target.clone(), self.values.len() - 1, &mut vec![], found);
+    }
 
-            //can drop end?
-            if next == element.value {
-                //Concat will return empty string
-                if next_idx == 0 {
-                    // we are done
-                    return expected;
+    // Works from the last value back to the first, undoing one operator at a time.
+    // `ops` holds the operators chosen so far, last one first; `found` is called with
+    // every complete sequence and returns false to stop the search.
+    fn reverse(
+        &self,
+        target: N,
+        idx: usize,
+        ops: &mut Ops<N>,
+        found: &mut dyn FnMut(&[&'static dyn Operator<N>]) -> bool,
+    ) -> bool {
+        if idx == 0 {
+            if target != self.values[0] {
+                return true;
+            }
+            let forward = ops.iter().rev().copied().collect::<Vec<_>>();
+            return found(&forward);
+        }
+        let (value, size) = (&self.values[idx], self.sizes[idx]);
+        for &op in self.allowed {
+            let more = match op.invert(&target, value, size) {
+                Inverse::Left(left) if left > N::zero() || !self.positive => {
+                    ops.push(op);
+                    let more = self.reverse(left, idx - 1, ops, found);
+                    ops.pop();
+                    more
                 }
-            } else if let Some(pos) = element.drop(next, size) {
-                if pos.value > 0 {
-                    queue.push(pos);
-                } else if pos.value == 0 && next_idx == 0 {
-                    return expected;
+                Inverse::Left(_) | Inverse::Impossible => true,
+                Inverse::Overflow => {
+                    self.overflow.set(true);
+                    true
                 }
+                Inverse::Forward => {
+                    let suffix = ops.iter().rev().copied().collect::<Vec<_>>();
+                    self.forward(self.values[0].clone(), 1, idx, &mut vec![], &mut |left, prefix| {
+                        match op.apply(&left, value, size) {
+                            Outcome::Value(result) if result == target => {
+                                let all = prefix.iter().copied()
+                                    .chain([op])
+                                    .chain(suffix.iter().copied())
+                                    .collect::<Vec<_>>();
+                                found(&all)
+                            }
+                            Outcome::Overflow => {
+                                self.overflow.set(true);
+                                true
+                            }
+                            _ => true,
+                        }
+                    })
+                }
+            };
+            if !more {
+                return false;
             }
+        }
+        true
+    }
 
-            // can subtract?
-            if let Some(pos) = element.sub(next) {
-                if pos.value > 0 {
-                    queue.push(pos);
-                } else if pos.value == 1 && next_idx == 0 {
-                    return expected;
+    // every value of `values[..end]` with the operators leading to it
+    fn forward(
+        &self,
+        value: N,
+        idx: usize,
+        end: usize,
+        ops: &mut Ops<N>,
+        found: &mut dyn FnMut(N, &Ops<N>) -> bool,
+    ) -> bool {
+        if idx == end {
+            return found(value, ops);
+        }
+        for &op in self.allowed {
+            match op.apply(&value, &self.values[idx], self.sizes[idx]) {
+                Outcome::Value(next) => {
+                    ops.push(op);
+                    let more = self.forward(next, idx + 1, end, ops, found);
+                    ops.pop();
+                    if !more {
+                        return false;
+                    }
                 }
+                Outcome::Overflow => self.overflow.set(true),
+                Outcome::Undefined => {}
             }
         }
+        true
     }
-    0
-}
-
-
-// impl Pos {
-//     fn new(value: i64, idx: usize) -> Self {
-//         Pos { value, idx }
-//     }
-//
-//     fn mul(&self, other: i64) -> Self {
-//         Pos {
-//             value: self.value * other,
-//             idx: self.idx + 1,
-//         }
-//     }
-//
-//     fn sum(&self, other: i64) -> Self {
-//         Pos {
-//             value: self.value + other,
-//             idx: self.idx + 1,
-//         }
-//     }
-//
-//     fn concat(&self, other: i64, size: usize) -> Self {
-//         let val = self.value * (i64::pow(10, size as u32)) + other;
-//         Pos {
-//             value: val,
-//             idx: self.idx + 1,
-//         }
-//     }
-// }
-
-
-// fn part1_queue_stack(expected: i64, values: &[i64]) -> i64 {
-//     let mut queue: VecDeque<Pos> = VecDeque::new();
-//     let start = Pos::new(values[0], 0);
-//     queue.push_back(start);
-//     while let Some(element) = queue.pop_back() {
-//         if element.value > expected {
-//             continue;
-//         } else if element.value == expected && element.idx == values.len() - 1 {
-//             return expected;
-//         } else if element.idx < values.len() - 1 {
-//             let next = values[element.idx + 1];
-//             let mul_pos = element.mul(next);
-//             if mul_pos.value <= expected {
-//                 queue.push_back(mul_pos);
-//             }
-//             let sum_pos = element.sum(next);
-//             if sum_pos.value <= expected {
-//                 queue.push_back(sum_pos);
-//             }
-//         }
-//     }
-//     0
-// }
-
-// fn part2_queue_stack(expected: i64, values: &[i64], sizes: &[usize]) -> i64 {
-//     let mut queue: Vec<Pos> = Vec::new();
-//     let start = Pos::new(values[0], 0);
-//     queue.push(start);
-//     while let Some(element) = queue.pop() {
-//         if element.value > expected {
-//             continue;
-//         } else if element.value == expected && element.idx == sizes.len() - 1 {
-//             return expected;
-//         } else if element.idx < sizes.len() - 1 {
-//             let size = sizes[element.idx + 1];
-//             let next = values[element.idx + 1];
-//             let mul_pos = element.mul(next);
-//             if mul_pos.value <= expected {
-//                 queue.push(mul_pos);
-//             }
-//             let sum_pos = element.sum(next);
-//             if sum_pos.value <= expected {
-//                 queue.push(sum_pos);
-//             }
-//             let concat_pos = element.concat(next, size);
-//             if concat_pos.value <= expected {
-//                 queue.push(concat_pos);
-//             }
-//         }
-//     }
-//     0
-// }
-
-
-// fn part1_slow(input: Input) -> i64 {
-//     input.expressions.iter().fold(0, |acc, expr: &Expression| {
-//         let val = part1_rec(0, 0, Operand::Mul, expr.expected, &expr.values);
-//         if val > 0 {
-//             acc + expr.expected
-//         } else {
-//             acc
-//         }
-//     })
-// }
-//
-//
-// #[derive(Eq, PartialEq)]
-// enum Operand {
-//     Plus,
-//     Mul,
-// }
-//
-// fn part1_rec(i: usize, value: i64, expr: Operand, expected: i64, values: &Vec<String>) -> i64 {
-//     if i == values.len() {
-//         if value == expected {
-//             expected
-//         } else {
-//             0
-//         }
-//     } else {
-//         let current = i64::from_str(values[i].as_str()).expect("cannot parse value");
-//         let value =
-//             if i == 0 {
-//                 current
-//             } else {
-//                 match expr {
-//                     Operand::Plus => value + current,
-//                     Operand::Mul => value * current,
-//                 }
-//             };
-//         part1_rec(i + 1, value, Operand::Mul, expected, values)
-//             +
-//             part1_rec(i + 1, value, Operand::Plus, expected, values)
-//     }
-// }
-//
-//
-// fn part2_slow(input: Input) -> i64 {
-//     input.expressions.iter().fold(0, |acc, expr: &Expression| {
-//         // if expr.expected != 7290 {
-//         //     return acc;
-//         // }
-//         let val = part2_rec(0, "".to_string(), OperandMII::Concat, expr.expected, &expr.values);
-//         // println!("Expected: {}, values: {:?}, Result: {}", expr.expected, expr.values, val);
-//         if val {
-//             acc + expr.expected
-//         } else {
-//             acc
-//         }
-//     })
-// }
-//
-// #[derive(Eq, PartialEq, Debug)]
-// enum OperandMII {
-//     Plus,
-//     Mul,
-//     Concat,
-// }
-// fn part2_rec(i: usize, value: String, expr: OperandMII, expected: i64, values: &Vec<String>) -> bool {
-//     if i == values.len() {
-//         let val = i64::from_str(value.as_str()).expect("cannot parse value");
-//         val == expected
-//     } else {
-//         let new_value = if i == 0 {
-//             values[i].clone()
-//         } else {
-//             let current = i64::from_str(value.as_str()).expect("cannot parse value");
-//             let new = i64::from_str(values[i].as_str()).expect("cannot parse value");
-//             if current > expected {
-//                 return false;
-//             }
-//             match expr {
-//                 OperandMII::Plus =>
-//                     (current + new).to_string(),
-//                 OperandMII::Mul =>
-//                     (current * new).to_string(),
-//                 OperandMII::Concat => {
-//                     let mut tmp = value.clone();
-//                     tmp.push_str(values[i].as_str());
-//                     tmp
-//                 }
-//             }
-//         };
-//         // println!("Expr {:?}, value: {:?}, curr: {}", expr, value.clone(), values[i]);
-//         let result = part2_rec(i + 1, new_value.clone(), OperandMII::Mul, expected, values)
-//             ||
-//             part2_rec(i + 1, new_value.clone(), OperandMII::Plus, expected, values)
-//             ||
-//             part2_rec(i + 1, new_value, OperandMII::Concat, expected, values);
-//         result
-//     }
-// }
+}
+
+/// Up to `limit` operator sequences that make the equation true. Fails only if
+/// none were found and a branch had to be dropped because of an overflow.
+fn witnesses<N: Num>(
+    expr: &Expression,
+    allowed: &[&'static dyn Operator<N>],
+    limit: usize,
+) -> Result<Vec<Ops<N>>, Overflow> {
+    let mut found = vec![];
+    let search = Search::new(expr, allowed)?;
+    if limit > 0 {
+        search.run(&mut |ops| {
+            found.push(ops.to_vec());
+            found.len() < limit
+        });
+    }
+    if found.is_empty() && search.overflow.get() {
+        return Err(Overflow::new(expr));
+    }
+    Ok(found)
+}
+
+fn count<N: Num>(expr: &Expression, allowed: &[&'static dyn Operator<N>]) -> Result<u64, Overflow> {
+    let mut count = 0;
+    let search = Search::new(expr, allowed)?;
+    search.run(&mut |_| {
+        count += 1;
+        true
+    });
+    if search.overflow.get() {
+        return Err(Overflow::new(expr));
+    }
+    Ok(count)
+}
+
+/// The equation written out with `ops`, e.g. `81 + 40 * 27`.
+fn annotate<N>(expr: &Expression, ops: &[&'static dyn Operator<N>]) -> String {
+    let mut out = expr.values[0].to_string();
+    for (op, value) in ops.iter().zip(expr.values[1..].iter()) {
+        out.push_str(&format!(" {} {}", op.symbol(), value));
+    }
+    out
+}
+
+/// One line per equation with a witness and the number of solutions,
+/// or every witness if `all` is set.
+fn report<N: Num>(input: &Input, allowed: &[&'static dyn Operator<N>], all: bool) -> Vec<String> {
+    input.expressions.iter().map(|expr| {
+        let limit = if all { usize::MAX } else { 1 };
+        let found = match witnesses(expr, allowed, limit) {
+            Ok(found) => found,
+            Err(err) => return err.to_string(),
+        };
+        if found.is_empty() {
+            return format!("{}: no solution", expr.expected);
+        }
+        let shown = found.iter().map(|ops| annotate(expr, ops)).collect::<Vec<_>>();
+        let count = match count(expr, allowed) {
+            Ok(1) => "1 solution".to_string(),
+            Ok(c) => format!("{} solutions", c),
+            Err(_) => "some solutions".to_string(),
+        };
+        format!("{}: {} ({})", expr.expected, shown.join(" | "), count)
+    }).collect()
+}
 
 #[cfg(test)]
 mod tests {
@@ -410,4 +660,57 @@ mod tests {
         let input = Input::from_str(s.as_str()).expect("cannot parse input");
         let _res = part2(input);
     }
+
+    fn annotated(expr: &Expression, ops: &[&'static dyn Operator<i128>]) -> Vec<String> {
+        witnesses(expr, ops, usize::MAX).unwrap().iter().map(|w| annotate(expr, w)).collect()
+    }
+
+    #[test]
+    fn test_witnesses() {
+        let input = input();
+        let all = operators("+,*,||").unwrap();
+        let expr = &input.expressions[1];
+        assert_eq!(vec!["81 * 40 + 27", "81 + 40 * 27"], annotated(expr, &all));
+        assert_eq!(Ok(2), count(expr, &all));
+        assert!(witnesses(&input.expressions[2], &all, 1).unwrap().is_empty());
+
+        let report = report(&input, &all, false);
+        assert_eq!("190: 10 * 19 (1 solution)", report[0]);
+        assert_eq!("3267: 81 * 40 + 27 (2 solutions)", report[1]);
+        assert_eq!("83: no solution", report[2]);
+        assert_eq!("7290: 6 * 8 || 6 * 15 (1 solution)", report[4]);
+    }
+
+    #[test]
+    fn test_operators() {
+        let input = Input::from_str("1: 5 4\n8: 2 3\n7: 9 2 2\n4: 3 1 4\n6: 1 2 3 1\n").unwrap();
+        let ops = operators("-,^,max,xor").unwrap();
+        assert_eq!(vec!["5 - 4", "5 xor 4"], annotated(&input.expressions[0], &ops));
+        assert_eq!(vec!["2 ^ 3"], annotated(&input.expressions[1], &ops));
+        assert_eq!(vec!["9 max 2 - 2", "9 - 2 max 2"], annotated(&input.expressions[2], &ops));
+        assert_eq!(
+            vec!["3 - 1 max 4", "3 ^ 1 max 4", "3 max 1 max 4", "3 xor 1 max 4"],
+            annotated(&input.expressions[3], &ops)
+        );
+        assert_eq!(Ok(3), count(&input.expressions[4], &operators::<i128>("+,*").unwrap()));
+        assert!(operators::<i128>("+,/").is_err());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let input = Input::from_str("0: 99999999999 99999999999 0\n").unwrap();
+        let expr = &input.expressions[0];
+        let small = witnesses(expr, &operators::<i64>("*").unwrap(), 1);
+        assert_eq!(Err(Overflow { expected: "0".to_string() }), small.map(|w| w.len()));
+        assert_eq!(Ok(1), witnesses(expr, &operators::<i128>("*").unwrap(), 1).map(|w| w.len()));
+
+        let big = "123456789012345678901234567890123456789012345";
+        let line = format!("{}: 123456789012345678901234567 890123456789012345\n", big);
+        let input = Input::from_str(&line).unwrap();
+        assert!(calibration(&input, &operators::<i128>("+,||").unwrap()).is_err());
+        assert_eq!(Ok(Answer::Big(big.parse().unwrap())), calibrate(&input, "+,||"));
+        let input = Input::from_str("1: 99 99 99 99\n").unwrap();
+        assert_eq!(Err(Overflow { expected: "1".to_string() }), calibrate(&input, "^"));
+        assert_eq!("equation 0 overflows", Overflow { expected: "0".to_string() }.to_string());
+    }
 }
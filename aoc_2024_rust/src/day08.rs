@@ -1,5 +1,9 @@
-use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use crate::answer::Answer;
+use crate::cli;
+use crate::math::{gcd, lcm};
+use crate::parse::{number, ParseError};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
 use std::fs;
 use std::str::FromStr;
 
@@ -10,211 +14,381 @@ pub fn print() {
     println!("Day 8");
     println!("Part 1 {}", part1(input.clone()));
     println!("Part 2 {}", part2(input.clone()));
+
+    // `--space torus=50,50` or `--space radius=100` instead of the map bounds
+    if let Some(space) = cli::options("--space").last() {
+        let space = space.parse().expect("cannot parse space");
+        let input = input.clone().with_space(space).expect("space doesn't fit the antennas");
+        println!("In {:?}: {} and {}", input.space, part1(input.clone()), part2(input.clone()));
+    }
+
+    // e.g. `--harmonic 3:1 --harmonic 1:1 --interior`
+    let ratios = cli::options("--harmonic").iter()
+        .map(|r| {
+            let (n, m) = r.split_once(':').expect("ratio is n:m");
+            (n.parse().expect("n is a number"), m.parse().expect("m is a number"))
+        })
+        .collect::<Vec<(i64, i64)>>();
+    if !ratios.is_empty() {
+        let mode = Mode::Harmonic {
+            ratios,
+            interior: cli::flag("--interior"),
+        };
+        println!("Harmonic {}", antinode_set(&input, &mode).len());
+    }
+
+    // `--freq A --freq 0` keeps only those frequencies, all of them by default
+    let freqs = cli::options("--freq").iter()
+        .map(|f| f.chars().next().expect("frequency is a char"))
+        .collect::<Vec<char>>();
+    for (name, mode) in [("Part 1", part1_mode()), ("Part 2", Mode::Line)] {
+        let mut sets = by_frequency(&input, &mode);
+        if !freqs.is_empty() {
+            sets.retain(|signal, _| freqs.contains(signal));
+        }
+        if sets.len() > 1 {
+            println!("{} overlaps", name);
+            print!("{}", overlap_table(&sets));
+        }
+        if cli::flag("--map") && !input.map.is_empty() {
+            println!("{} antinodes", name);
+            print!("{}", render(&input.map, &sets.into_values().flatten().collect()));
+        }
+    }
 }
 
+/// Integer point with any number of coordinates.
+type Point = Vec<i64>;
+
 #[derive(Clone)]
 struct Input {
-    raw: String,
+    // empty when the antennas come as records
     map: Vec<Vec<char>>,
+    antennas: Vec<Antennae>,
+    space: Space,
 }
 
 
 #[derive(Debug)]
-enum InputError {}
+enum InputError {
+    Parse(ParseError),
+}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::Parse(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<ParseError> for InputError {
+    fn from(err: ParseError) -> Self {
+        InputError::Parse(err)
+    }
+}
 
 impl FromStr for Input {
     type Err = InputError;
 
+    // either the puzzle map or `freq x y z` records, one antenna per line
     fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if input.lines().any(|line| line.trim().contains(char::is_whitespace)) {
+            return Input::from_records(input);
+        }
         let map = input.lines()
             .map(|line| {
                 line.chars().collect::<Vec<char>>()
             })
             .collect::<Vec<Vec<char>>>();
+        let antennas = antennas(&map);
+        let space = Space::Bounded(vec![map.len() as i64, map.first().map_or(0, |l| l.len()) as i64]);
         Ok(Input {
-            raw: input.to_string(),
             map,
+            antennas,
+            space,
         })
     }
 }
 
+impl Input {
+    /// The same antennas in another space, which must have as many dimensions.
+    fn with_space(self, space: Space) -> Result<Self, ParseError> {
+        let dims = self.antennas.first().map(|a| a.pos.len());
+        if space.dims().is_some_and(|n| dims.is_some_and(|dims| dims != n)) {
+            return Err(ParseError::Malformed(format!("{:?} for {} dimensions", space, dims.unwrap_or(0))));
+        }
+        Ok(Input {
+            space,
+            ..self
+        })
+    }
 
-#[derive(Eq, PartialEq, Hash)]
-struct Pos {
-    x: usize,
-    y: usize,
+    // the space is the smallest box from the origin holding every antenna
+    fn from_records(input: &str) -> Result<Self, InputError> {
+        let mut antennas = vec![];
+        for line in input.lines().filter(|line| !line.trim().is_empty()) {
+            let mut tokens = line.split_whitespace();
+            let signal = match tokens.next().map(|t| t.chars().collect::<Vec<_>>()).as_deref() {
+                Some([signal]) => *signal,
+                _ => return Err(ParseError::Malformed(line.to_string()).into()),
+            };
+            let pos = tokens.map(number).collect::<Result<Point, _>>()?;
+            if pos.is_empty() || antennas.first().is_some_and(|a: &Antennae| a.pos.len() != pos.len()) {
+                return Err(ParseError::Malformed(line.to_string()).into());
+            }
+            antennas.push(Antennae {
+                signal,
+                pos,
+            });
+        }
+        let dims = antennas.first().map_or(0, |a| a.pos.len());
+        let sizes = (0..dims).map(|i| antennas.iter().map(|a| a.pos[i] + 1).max().unwrap_or(0)).collect();
+        Ok(Input {
+            map: vec![],
+            antennas,
+            space: Space::Bounded(sizes),
+        })
+    }
 }
 
-#[derive(Eq, PartialEq, Hash)]
+
+#[derive(Clone, Eq, PartialEq, Hash)]
 struct Antennae {
     signal: char,
-    pos: Pos,
+    pos: Point,
 }
 
+/// Where antinodes can be, sizes and points have one entry per dimension.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Space {
+    /// Points with `0 <= p[i] < size[i]`.
+    Bounded(Vec<i64>),
+    /// Same sizes, but every coordinate wraps around.
+    Torus(Vec<i64>),
+    /// Points at most `r` away from the origin, however far the grid goes.
+    Radius(i64),
+}
+
+impl FromStr for Space {
+    type Err = ParseError;
 
-impl PartialOrd for Pos {
-    fn partial_cmp(&self, other: &Pos) -> Option<Ordering> {
-        Some(self.cmp(other))
+    // `bounds=50,50`, `torus=10,10,10` or `radius=20`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || ParseError::Malformed(s.to_string());
+        let (kind, value) = s.split_once('=').ok_or_else(malformed)?;
+        let sizes = || {
+            let sizes = value.split(',').map(number).collect::<Result<Vec<i64>, _>>()?;
+            match sizes.iter().all(|&size| size > 0) {
+                true => Ok(sizes),
+                false => Err(malformed()),
+            }
+        };
+        match kind {
+            "bounds" => Ok(Space::Bounded(sizes()?)),
+            "torus" => Ok(Space::Torus(sizes()?)),
+            // `r * r` has to fit, so `place` can compare squared distances
+            "radius" => match number::<i64>(value)? {
+                r if r >= 0 && r.checked_mul(r).is_some() => Ok(Space::Radius(r)),
+                _ => Err(malformed()),
+            },
+            _ => Err(malformed()),
+        }
     }
 }
 
-impl Ord for Pos {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.x.cmp(&other.x).then(self.y.cmp(&other.y))
+impl Space {
+    /// Number of coordinates of the points, any for a radius.
+    fn dims(&self) -> Option<usize> {
+        match self {
+            Space::Bounded(sizes) | Space::Torus(sizes) => Some(sizes.len()),
+            Space::Radius(_) => None,
+        }
     }
-}
 
-fn part1(input: Input) -> i64 {
-    let max_x = input.map.len() as i32;
-    let max_y = input.map[0].len() as i32;
-    let mut antennas = HashMap::new();
-    input.raw.lines().enumerate().for_each(|(x, line)| {
-        line.chars().enumerate().for_each(|(y, c)| {
-            if c != '.' {
-                let antennae = Antennae {
-                    signal: c,
-                    pos: Pos { x, y },
-                };
-                antennas.entry(c).or_insert(Vec::new()).push(antennae);
+    /// The point as it lies in the space, wrapped on a torus; `None` if it's outside.
+    fn place(&self, p: Point) -> Option<Point> {
+        debug_assert!(self.dims().is_none_or(|dims| dims == p.len()), "point {:?} not in {:?}", p, self);
+        match self {
+            Space::Bounded(sizes) => p.iter().zip(sizes).all(|(&v, &size)| v >= 0 && v < size).then_some(p),
+            Space::Torus(sizes) => Some(p.iter().zip(sizes).map(|(v, size)| v.rem_euclid(*size)).collect()),
+            Space::Radius(r) => {
+                // a squared distance that doesn't fit is certainly beyond `r * r`
+                let norm = p.iter().try_fold(0_i64, |acc, v| v.checked_mul(*v)?.checked_add(acc));
+                norm.is_some_and(|norm| norm <= r * r).then_some(p)
             }
-        })
-    });
-    // println!("max_x {}", max_x);
-    // println!("max_y {}", max_y);
-    //    y1 y2 y3
-    // x1 u     w
-    // x2
-    // x3 w     u
-    let mut antipods = HashSet::new();
-    antennas.iter().for_each(|(_, lst)| {
-        for a in lst.iter() {
-            for b in lst.iter() {
-                if *a == *b || b.pos > a.pos {
-                    continue;
-                }
-                let ax = a.pos.x as i32;
-                let bx = b.pos.x as i32;
-                let dx = ax - bx;
-                let ay = a.pos.y as i32;
-                let by = b.pos.y as i32;
-                let dy = ay - by;
-
-                let s1x = ax + dx;
-                let s1y = ay + dy;
-                // let mut map = input.map.clone();
-                // map[a.pos.x][a.pos.y] = '*';
-                // map[b.pos.x][b.pos.y] = '$';
-                // println!("antipod: {:?}", (s1x, s1y));
-                if s1x >= 0 && s1x < max_x && s1y >= 0 && s1y < max_y {
-                    antipods.insert((s1x, s1y));
-                    // map[s1x as usize][s1y as usize] = '#';
-                }
+        }
+    }
 
-                let s2x = bx - dx;
-                let s2y = by - dy;
-                // println!("antipod: {:?}", (s2x, s2y));
-                if s2x >= 0 && s2x < max_x && s2y >= 0 && s2y < max_y {
-                    antipods.insert((s2x, s2y));
-                    // map[s2x as usize][s2y as usize] = '#';
+    /// Range of `k` for which `a + k * step` can be in the space. On a torus the
+    /// line comes back to `a` after one period, elsewhere it leaves for good.
+    fn span(&self, a: &[i64], step: &[i64]) -> Option<(i64, i64)> {
+        debug_assert!(self.dims().is_none_or(|dims| dims == a.len()), "point {:?} not in {:?}", a, self);
+        match self {
+            Space::Bounded(sizes) => {
+                let (mut lo, mut hi) = (i64::MIN, i64::MAX);
+                for ((&a, &s), &size) in a.iter().zip(step).zip(sizes) {
+                    // a + k * s in 0..size, flipped so the divisor is positive
+                    let (from, to, s) = if s < 0 { (a - size + 1, a, -s) } else { (-a, size - 1 - a, s) };
+                    if s == 0 {
+                        if from > 0 || to < 0 {
+                            return None;
+                        }
+                        continue;
+                    }
+                    lo = lo.max(-(-from).div_euclid(s));
+                    hi = hi.min(to.div_euclid(s));
                 }
-                // map.iter().for_each(|row| {
-                //     row.iter().for_each(|c| {
-                //         print!("{}", c);
-                //     });
-                //     println!();
-                // });
+                (lo <= hi).then_some((lo, hi))
+            }
+            Space::Torus(sizes) => {
+                let period = step.iter().zip(sizes).fold(1, |acc, (&s, &size)| lcm(acc, size / gcd(s, size)));
+                Some((0, period - 1))
+            }
+            Space::Radius(r) => {
+                // |a + k * step|^2 <= r^2 is a quadratic in k, rounded out and checked by `place`
+                let dot = |u: &[i64], v: &[i64]| u.iter().zip(v).map(|(x, y)| (x * y) as f64).sum::<f64>();
+                let (qa, qb, qc) = (dot(step, step), dot(a, step), dot(a, a) - (*r as f64).powi(2));
+                let disc = qb * qb - qa * qc;
+                if disc < 0.0 {
+                    return None;
+                }
+                let lo = ((-qb - disc.sqrt()) / qa).floor() as i64 - 1;
+                let hi = ((-qb + disc.sqrt()) / qa).ceil() as i64 + 1;
+                Some((lo, hi))
             }
         }
-    });
-    antipods.len() as i64
+    }
 }
 
-fn part2(input: Input) -> i64 {
-    let max_x = input.map.len() as i32;
-    let max_y = input.map[0].len() as i32;
-    let mut antennas = HashMap::new();
-    input.raw.lines().enumerate().for_each(|(x, line)| {
-        line.chars().enumerate().for_each(|(y, c)| {
-            if c != '.' {
-                let antennae = Antennae {
-                    signal: c,
-                    pos: Pos { x, y },
-                };
-                antennas.entry(c).or_insert(Vec::new()).push(antennae);
-            }
-        })
-    });
-    // println!("max_x {}", max_x);
-    // println!("max_y {}", max_y);
-    //    y1 y2 y3
-    // x1 u     w
-    // x2
-    // x3 w     u
-    // let mut map = input.map.clone();
-    let mut antipods = HashSet::new();
-    antennas.iter().for_each(|(_, lst)| {
+/// Which points in line with a pair of antennas are antinodes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Mode {
+    /// Points where one antenna is `n / m` times as far away as the other, for
+    /// every `(n, m)`. With `interior` also the points between the two antennas.
+    Harmonic {
+        ratios: Vec<(i64, i64)>,
+        interior: bool,
+    },
+    /// Every grid point in line with the two antennas.
+    Line,
+}
+
+// one antenna twice as far as the other, never between them
+fn part1_mode() -> Mode {
+    Mode::Harmonic {
+        ratios: vec![(2, 1)],
+        interior: false,
+    }
+}
+
+fn part1(input: Input) -> Answer {
+    antinode_set(&input, &part1_mode()).len().into()
+}
+
+fn part2(input: Input) -> Answer {
+    antinode_set(&input, &Mode::Line).len().into()
+}
+
+fn antennas(map: &[Vec<char>]) -> Vec<Antennae> {
+    map.iter().enumerate().flat_map(|(x, line)| {
+        line.iter().enumerate()
+            .filter(|(_, &c)| c != '.')
+            .map(move |(y, &c)| Antennae {
+                signal: c,
+                pos: vec![x as i64, y as i64],
+            })
+    }).collect()
+}
+
+fn antinode_set(input: &Input, mode: &Mode) -> HashSet<Point> {
+    by_frequency(input, mode).into_values().flatten().collect()
+}
+
+/// Antinodes of every frequency on its own, frequencies can share antinodes.
+fn by_frequency(input: &Input, mode: &Mode) -> BTreeMap<char, HashSet<Point>> {
+    let mut groups: BTreeMap<char, Vec<&Antennae>> = BTreeMap::new();
+    input.antennas.iter().for_each(|a| groups.entry(a.signal).or_default().push(a));
+    groups.into_iter().map(|(signal, lst)| {
+        let mut antipods = HashSet::new();
         for a in lst.iter() {
             for b in lst.iter() {
-                if *a == *b || b.pos > a.pos {
+                if a.pos >= b.pos {
                     continue;
                 }
-                let ax = a.pos.x as i32;
-                let bx = b.pos.x as i32;
-                let dx = ax - bx;
-                let ay = a.pos.y as i32;
-                let by = b.pos.y as i32;
-                let dy = ay - by;
-                antipods.insert((ax, ay));
-                antipods.insert((bx, by));
-
-                // map[a.pos.x][a.pos.y] = '*';
-                // map[b.pos.x][b.pos.y] = '$';
-                let mut s1x = ax;
-                let mut s1y = ay;
-                loop {
-                    s1x += dx;
-                    s1y += dy;
-                    if s1x >= 0 && s1x < max_x && s1y >= 0 && s1y < max_y {
-                        // println!("antipod: {:?}", (s1x, s1y));
-                        antipods.insert((s1x, s1y));
-                        // map[s1x as usize][s1y as usize] = '#';
-                    } else {
-                        break;
-                    }
-                }
+                antipods.extend(antinodes(&a.pos, &b.pos, mode, &input.space));
+            }
+        }
+        (signal, antipods)
+    }).collect()
+}
+
+/// Shared antinodes of every pair of frequencies, in the order of `sets`;
+/// the diagonal holds the number of antinodes of each frequency.
+fn overlaps(sets: &BTreeMap<char, HashSet<Point>>) -> Vec<Vec<usize>> {
+    sets.values().map(|a| {
+        sets.values().map(|b| a.intersection(b).count()).collect()
+    }).collect()
+}
 
+fn overlap_table(sets: &BTreeMap<char, HashSet<Point>>) -> String {
+    let matrix = overlaps(sets);
+    let width = matrix.iter().flatten().map(|v| v.to_string().len()).max().unwrap_or(1);
+    let mut out = " ".to_string();
+    for signal in sets.keys() {
+        out.push_str(&format!(" {:>w$}", signal, w = width));
+    }
+    out.push('\n');
+    for (signal, row) in sets.keys().zip(matrix.iter()) {
+        out.push(*signal);
+        for v in row {
+            out.push_str(&format!(" {:>w$}", v, w = width));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The map with `#` on antinodes, antennas stay on top as in the puzzle text.
+fn render(map: &[Vec<char>], antinodes: &HashSet<Point>) -> String {
+    map.iter().enumerate().map(|(x, line)| {
+        line.iter().enumerate().map(|(y, &c)| {
+            if c == '.' && antinodes.contains(&vec![x as i64, y as i64]) { '#' } else { c }
+        }).chain(['\n']).collect::<String>()
+    }).collect()
+}
 
-                let mut s2x = bx;
-                let mut s2y = by;
-                loop {
-                    s2x -= dx;
-                    s2y -= dy;
-                    if s2x >= 0 && s2x < max_x && s2y >= 0 && s2y < max_y {
-                        // println!("antipod: {:?}", (s2x, s2y));
-                        antipods.insert((s2x, s2y));
-                        // map[s2x as usize][s2y as usize] = '#';
-                    } else {
-                        break;
+/// Antinodes of two distinct antennas. Points on the line are `a + t * (b - a)`;
+/// one is `n / m` times as far from `a` as from `b` for `t = n / (n - m)` outside
+/// the pair and `t = n / (n + m)` between them. Only grid points count, and those
+/// are the multiples of `b - a` reduced by its gcd.
+fn antinodes(a: &[i64], b: &[i64], mode: &Mode, space: &Space) -> Vec<Point> {
+    let delta = a.iter().zip(b).map(|(a, b)| b - a).collect::<Vec<_>>();
+    let g = delta.iter().fold(0, |acc, &d| gcd(acc, d));
+    let step = delta.iter().map(|d| d / g).collect::<Vec<_>>();
+    let at = |k: i64| a.iter().zip(&step).map(|(a, s)| a + k * s).collect::<Point>();
+    let mut found = vec![];
+    match mode {
+        Mode::Line => {
+            if let Some((lo, hi)) = space.span(a, &step) {
+                found.extend((lo..=hi).filter_map(|k| space.place(at(k))));
+            }
+        }
+        Mode::Harmonic { ratios, interior } => {
+            for &(n, m) in ratios {
+                let mut fractions = vec![(n, n - m), (m, m - n)];
+                if *interior {
+                    fractions.extend([(n, n + m), (m, n + m)]);
+                }
+                for (p, q) in fractions {
+                    if q != 0 && (p * g) % q == 0 {
+                        found.extend(space.place(at(p * g / q)));
                     }
                 }
-
-                // map.iter().for_each(|row| {
-                //     row.iter().for_each(|c| {
-                //         print!("{}", c);
-                //     });
-                //     println!();
-                // });
             }
         }
-    });
-    // println!("{:?}", antipods);
-    // map.iter().for_each(|row| {
-    //     row.iter().for_each(|c| {
-    //         print!("{}", c);
-    //     });
-    //     println!();
-    // });
-    antipods.len() as i64
+    }
+    found
 }
 
 #[cfg(test)]
@@ -251,6 +425,13 @@ mod tests {
         assert_eq!(34, res)
     }
 
+    #[test]
+    fn test_part2_gcd() {
+        let input = Input::from_str("a....\n.....\n....a\n").unwrap();
+        let res = part2(input);
+        assert_eq!(3, res)
+    }
+
     #[test]
     fn test_part2_2() {
         let s = fs::read_to_string("tests/08_2.txt").unwrap();
@@ -258,4 +439,67 @@ mod tests {
         let res = part2(input);
         assert_eq!(8, res)
     }
+
+    #[test]
+    fn test_harmonic() {
+        let input = Input::from_str("a.....a\n").unwrap();
+        let outside = Mode::Harmonic { ratios: vec![(2, 1)], interior: false };
+        let inside = Mode::Harmonic { ratios: vec![(2, 1)], interior: true };
+        assert!(antinode_set(&input, &outside).is_empty());
+        assert_eq!(HashSet::from([vec![0, 2], vec![0, 4]]), antinode_set(&input, &inside));
+
+        let input = Input::from_str("a.a......\n").unwrap();
+        let mode = Mode::Harmonic { ratios: vec![(3, 1), (1, 1)], interior: true };
+        assert_eq!(HashSet::from([vec![0, 3], vec![0, 1]]), antinode_set(&input, &mode));
+    }
+
+    #[test]
+    fn test_by_frequency() {
+        let input = input();
+        let sets = by_frequency(&input, &part1_mode());
+        assert_eq!(vec!['0', 'A'], sets.keys().copied().collect::<Vec<_>>());
+        assert_eq!(vec![vec![10, 1], vec![1, 5]], overlaps(&sets));
+        assert_eq!("   0  A\n0 10  1\nA  1  5\n", overlap_table(&sets));
+    }
+
+    #[test]
+    fn test_render() {
+        let expected = [
+            "......#....#", "...#....0...", "....#0....#.", "..#....0....",
+            "....0....#..", ".#....A.....", "...#........", "#......#....",
+            "........A...", ".........A..", "..........#.", "..........#.",
+        ];
+        let input = input();
+        let map = render(&input.map, &antinode_set(&input, &part1_mode()));
+        assert_eq!(expected.map(|line| format!("{}\n", line)).concat(), map);
+    }
+
+    #[test]
+    fn test_spaces() {
+        let input = Input::from_str("a 0 0 0\na 1 1 1\n").unwrap();
+        assert_eq!(Space::Bounded(vec![2, 2, 2]), input.space);
+        assert_eq!(2, part2(input.clone()));
+        assert!(input.clone().with_space("bounds=10,10".parse().unwrap()).is_err());
+        let input = input.with_space("bounds=4,4,4".parse().unwrap()).unwrap();
+        assert_eq!(1, part1(input.clone()));
+        assert_eq!(4, part2(input.clone()));
+
+        // the line wraps around after 5 steps along the second axis
+        let input = Input::from_str("a 0 0\na 0 1\n").unwrap();
+        let input = input.with_space("torus=3,5".parse().unwrap()).unwrap();
+        assert_eq!(HashSet::from([vec![0, 2], vec![0, 4]]), antinode_set(&input, &part1_mode()));
+        assert_eq!(5, part2(input.clone()));
+
+        let input = Input::from_str("a 0 0\na 1 1\nb -1 2\nb 1 2\n").unwrap();
+        let input = input.with_space("radius=3".parse().unwrap()).unwrap();
+        // k = -2..=2 along the diagonal, x = -2..=2 along y = 2, sharing (2, 2)
+        assert_eq!(5 + 5 - 1, part2(input.clone()));
+
+        assert!(Input::from_str("a 1 2\nb 1\n").is_err());
+        assert!(Input::from_str("ab 1 2\n").is_err());
+        assert!("sphere=3".parse::<Space>().is_err());
+        assert!("torus=3,0".parse::<Space>().is_err());
+        assert!("radius=4000000000".parse::<Space>().is_err());
+        assert_eq!(None, Space::Radius(3).place(vec![i64::MAX, 0]));
+    }
 }
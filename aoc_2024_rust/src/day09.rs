@@ -1,3 +1,5 @@
+use crate::answer::Answer;
+use crate::parse::{digits, ParseError};
 use std::fs;
 use std::str::FromStr;
 
@@ -15,16 +17,11 @@ struct Input {
     data: Vec<Chunk>,
 }
 
-#[derive(Debug)]
-enum InputError {}
-
 impl FromStr for Input {
-    type Err = InputError;
+    type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let data: Vec<u8> = input.trim().chars().map(|c|
-            u8::from_str(String::from(c).as_str()).expect(format!("cannot parse number: {}", c).as_str())
-        ).collect();
+        let data: Vec<u8> = digits(input)?;
 
         let parsed = data.chunks(2).enumerate().flat_map(|(i, v)| {
             let mut res = vec![];
@@ -53,7 +50,7 @@ struct Chunk {
     len: u8,
 }
 
-fn part1(input: Input) -> i64 {
+fn part1(input: Input) -> Answer {
     let mut parsed = input.data.clone();
     let mut start = 0;
     let mut end = parsed.len() - 1;
@@ -96,11 +93,11 @@ fn part1(input: Input) -> i64 {
     // println!("compacted: {:?}", &string);
     // println!("original_checksum: {}", checksum(&string));
 
-    checksum_value
+    checksum_value.into()
 }
 
 
-fn part2(input: Input) -> i64 {
+fn part2(input: Input) -> Answer {
     let mut parsed = input.data.clone();
     let mut start = 0;
     let mut max_end = parsed.len() - 1;
@@ -155,7 +152,7 @@ fn part2(input: Input) -> i64 {
     // println!("compacted: {:?}", &string);
     // println!("original_checksum: {}", checksum(&string));
 
-    checksum_value
+    checksum_value.into()
 }
 
 
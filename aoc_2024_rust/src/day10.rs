@@ -1,3 +1,5 @@
+use crate::answer::Answer;
+use crate::parse::{digit_grid, ParseError};
 use std::collections::{HashSet, VecDeque};
 use std::fs;
 use std::str::FromStr;
@@ -25,18 +27,15 @@ struct Pos {
     y: usize,
 }
 
-#[derive(Debug)]
-enum InputError {}
-
 impl FromStr for Input {
-    type Err = InputError;
+    type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let map: Vec<Vec<u8>> = digit_grid(input)?;
         let mut starts = vec![];
         let mut idx = 0;
-        let map: Vec<Vec<u8>> = input.lines().enumerate().map(|(x, line)|
-            line.chars().enumerate().map(|(y, c)| {
-                let height = u8::from_str(String::from(c).as_str()).expect(format!("cannot parse number: {}", c).as_str());
+        map.iter().enumerate().for_each(|(x, row)| {
+            row.iter().enumerate().for_each(|(y, &height)| {
                 if height == 0 {
                     starts.push(Pos {
                         idx,
@@ -45,9 +44,8 @@ impl FromStr for Input {
                     });
                     idx += 1;
                 }
-                height
-            }).collect()
-        ).collect();
+            })
+        });
 
         Ok(Input {
             map,
@@ -56,7 +54,7 @@ impl FromStr for Input {
     }
 }
 
-fn parts(input: Input) -> (i64, i64) {
+fn parts(input: Input) -> (Answer, Answer) {
     let map = &input.map;
     let mut queue = VecDeque::new();
     queue.extend(input.starts.iter().cloned());
@@ -109,7 +107,7 @@ fn parts(input: Input) -> (i64, i64) {
             )
         }
     }
-    (trails.len() as i64, num_trails as i64)
+    (trails.len().into(), num_trails.into())
 }
 
 #[cfg(test)]
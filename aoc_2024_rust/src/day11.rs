@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use crate::answer::Answer;
+use crate::parse::{list, ParseError};
+use crate::memo::Memo;
 use std::fs;
 use std::str::FromStr;
 pub fn print() {
@@ -15,16 +17,11 @@ struct Input {
     data: Vec<u64>,
 }
 
-#[derive(Debug)]
-enum InputError {}
-
 impl FromStr for Input {
-    type Err = InputError;
+    type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let data: Vec<u64> = input.trim().split(' ').map(|v| v.to_string()).map(|v| {
-            u64::from_str(v.as_str()).expect("cannot parse value")
-        }).collect();
+        let data: Vec<u64> = list(input, ' ')?;
         Ok(Input {
             data
         })
@@ -32,35 +29,35 @@ impl FromStr for Input {
 }
 
 
-fn part1(input: Input) -> i64 {
-    let mut index = HashMap::<(u64, u16), i64>::new();
+fn part1(input: Input) -> Answer {
+    let mut index = Memo::<(u64, u16), i64>::new();
 
     let max_idx = 25;
     input.data.iter().fold(0, |acc, &value| {
         acc + calculate_rec(value, 0, max_idx, &mut index)
-    })
+    }).into()
 }
 
 
-fn part2(input: Input) -> i64 {
-    let mut index = HashMap::<(u64, u16), i64>::new();
+fn part2(input: Input) -> Answer {
+    let mut index = Memo::<(u64, u16), i64>::new();
 
     let max_idx = 75;
     input.data.iter().fold(0, |acc, &value| {
         acc + calculate_rec(value, 0, max_idx, &mut index)
-    })
+    }).into()
 }
-fn calculate_rec(curr: u64, idx: u16, max_idx: u16, index: &mut HashMap<(u64, u16), i64>) -> i64 {
+fn calculate_rec(curr: u64, idx: u16, max_idx: u16, index: &mut Memo<(u64, u16), i64>) -> i64 {
     let mut num_found = 0;
     if idx == max_idx {
         return 1;
     }
-    if let Some(&value) = index.get(&(curr, max_idx - idx)) {
+    if let Some(value) = index.get(&(curr, max_idx - idx)) {
         return value;
     }
     if curr == 0 {
         num_found += calculate_rec(1, idx + 1, max_idx, index);
-    } else if curr.to_string().len() % 2 == 0 {
+    } else if curr.to_string().len().is_multiple_of(2) {
         let len = curr.to_string().len() as u32;
 
         let scale: u64 = u64::pow(10, len / 2);
@@ -70,8 +67,7 @@ fn calculate_rec(curr: u64, idx: u16, max_idx: u16, index: &mut HashMap<(u64, u1
     } else {
         num_found += calculate_rec(curr * 2024, idx + 1, max_idx, index);
     }
-    index.insert((curr, max_idx - idx), num_found);
-    num_found
+    index.insert((curr, max_idx - idx), num_found)
 }
 
 
@@ -1,5 +1,6 @@
+use crate::answer::Answer;
 use crate::day12::Dir::*;
-use std::collections::{HashMap, HashSet, VecDeque};
+use crate::union_find::{Merge, UnionFind};
 use std::fs;
 use std::str::FromStr;
 
@@ -36,7 +37,6 @@ impl FromStr for Input {
 
 #[derive(Clone, Hash, Eq, PartialEq, Debug)]
 struct Pos {
-    idx: u32,
     x: usize,
     y: usize,
 }
@@ -98,7 +98,6 @@ impl Pos {
 
         if next_x >= 0 && next_x <= x_max as i32 && next_y >= 0 && next_y <= y_max as i32 {
             let pos = Pos {
-                idx: self.idx,
                 x: next_x as usize,
                 y: next_y as usize,
             };
@@ -142,99 +141,61 @@ impl Pos {
     }
 }
 
-fn part1(input: Input) -> i64 {
-    let map = input.map;
-    let mut visited: HashSet<(usize, usize)> = HashSet::new();
-    let mut metrics: HashMap<u32, (u32, u32)> = HashMap::new();
-
-    let mut idx = 0;
-    let mut queue = VecDeque::new();
-
-    queue.push_back(Pos { idx, x: 0, y: 0 });
+#[derive(Default)]
+struct Region {
+    area: u32,
+    perimeter: u32,
+    corners: u32,
+}
 
-    while let Some(curr) = queue.pop_front() {
-        if visited.contains(&(curr.x, curr.y)) {
-            continue;
-        }
-        let mut curr_per = 0;
-        for next in curr.sides(&map) {
-            if let Some(pos) = next {
-                if visited.contains(&(curr.x, curr.y)) {
-                    continue;
-                }
-                if map[pos.x][pos.y] == map[curr.x][curr.y] {
-                    queue.push_front(pos);
-                } else {
-                    idx += 1;
-                    let new_pos = Pos {
-                        idx,
-                        x: pos.x,
-                        y: pos.y,
-                    };
-                    queue.push_back(new_pos);
-
-                    curr_per += 1;
-                }
-            } else {
-                curr_per += 1;
-            }
-        }
-        metrics.entry(curr.idx).and_modify(|(p, s)| {
-            *p += curr_per;
-            *s += 1;
-        }).or_insert((curr_per, 1));
-        visited.insert((curr.x, curr.y));
+impl Merge for Region {
+    fn merge(&mut self, other: Self) {
+        self.area += other.area;
+        self.perimeter += other.perimeter;
+        self.corners += other.corners;
     }
-
-    metrics.iter().fold(0, |acc, (_id, (per, sqr))| {
-        let cost = per * sqr;
-        acc + (cost as i64)
-    })
 }
 
-
-fn part2(input: Input) -> i64 {
-    let map = input.map;
-    let mut visited: HashMap<(usize, usize), u32> = HashMap::new();
-    let mut metrics: HashMap<u32, (u32, u32)> = HashMap::new();
-    let mut idx = 0;
-    let mut queue = VecDeque::new();
-
-    queue.push_back(Pos { idx, x: 0, y: 0 });
-
-    while let Some(curr) = queue.pop_front() {
-        if visited.contains_key(&(curr.x, curr.y)) {
-            continue;
+// every plot starts as its own region and is joined with equal neighbours to the right and below
+fn regions(map: &HikeMap) -> UnionFind<Region> {
+    let width = map[0].len();
+    let plots = map.iter().enumerate().flat_map(|(x, row)| {
+        (0..row.len()).map(move |y| Pos { x, y })
+    }).map(|pos| {
+        let perimeter = pos.sides(map).iter().filter(|side| {
+            side.as_ref().is_none_or(|next| next.value(map) != pos.value(map))
+        }).count() as u32;
+        Region {
+            area: 1,
+            perimeter,
+            corners: pos.num_corners(map),
         }
-        for pos in curr.sides(&map).into_iter().flatten() {
-            if visited.contains_key(&(curr.x, curr.y)) {
-                continue;
+    }).collect();
+    let mut regions = UnionFind::from_data(plots);
+    for (x, row) in map.iter().enumerate() {
+        for (y, plant) in row.iter().enumerate() {
+            if y + 1 < width && row[y + 1] == *plant {
+                regions.union(x * width + y, x * width + y + 1);
             }
-            if map[pos.x][pos.y] != map[curr.x][curr.y] {
-                idx += 1;
-                let new_pos = Pos {
-                    idx,
-                    x: pos.x,
-                    y: pos.y,
-                };
-                queue.push_back(new_pos);
-            } else {
-                queue.push_front(pos);
+            if x + 1 < map.len() && map[x + 1][y] == *plant {
+                regions.union(x * width + y, (x + 1) * width + y);
             }
         }
-        let num_corners = curr.num_corners(&map);
-
-        metrics.entry(curr.idx).and_modify(|(p, s)| {
-            *s += 1;
-            *p += num_corners;
-        }).or_insert((num_corners, 1));
-        visited.insert((curr.x, curr.y), curr.idx);
     }
+    regions
+}
+
+fn part1(input: Input) -> Answer {
+    regions(&input.map).roots().fold(0, |acc, (_, _, region)| {
+        acc + (region.area * region.perimeter) as i64
+    }).into()
+}
+
 
-    metrics.iter().fold(0, |acc, (_id, (per, sqr))| {
-        let cost = per * sqr;
-        acc + (cost as i64)
-    })
+fn part2(input: Input) -> Answer {
+    regions(&input.map).roots().fold(0, |acc, (_, _, region)| {
+        acc + (region.area * region.corners) as i64
+    }).into()
 }
 
 
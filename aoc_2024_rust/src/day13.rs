@@ -1,4 +1,6 @@
-use regex::Regex;
+use crate::answer::Answer;
+use crate::math::{min_cost_combination, solve_2x2};
+use crate::parse::{ints, sections, ParseError};
 use std::fs;
 use std::str::FromStr;
 
@@ -17,10 +19,6 @@ struct Input {
     quizzes: Vec<Quiz>,
 }
 
-#[derive(Debug)]
-enum InputError {}
-
-
 #[derive(Clone)]
 struct Pos {
     x: i64,
@@ -44,100 +42,76 @@ struct Quiz {
     target: Pos,
 }
 
-fn parse_button(string: &str, re: &Regex) -> (i64, i64) {
-    let (x, y) = re.captures(string).map(|c| {
-        let x_sign = c.name("x_sign").expect("cannot parse sign");
-        let x_string = c.name("x").expect("cannot parse sign");
-        let y_sign = c.name("y_sign").expect("cannot parse sign");
-        let y_string = c.name("y").expect("cannot parse sign");
-        let mut x = i64::from_str(x_string.as_str()).expect("cannot parse x");
-        if x_sign.as_str() == "-" {
-            x *= -1;
-        }
-        let mut y = i64::from_str(y_string.as_str()).expect("cannot parse x");
-        if y_sign.as_str() == "-" {
-            y *= -1;
-        }
-        (x, y)
-    }).unwrap();
-    (x, y)
-}
-
-fn parse_target(string: &str, re: &Regex) -> (i64, i64) {
-    let (x, y) = re.captures(string).map(|c| {
-        let x_string = c.name("x").expect("cannot parse sign");
-
-        let y_string = c.name("y").expect("cannot parse sign");
-        let x = i64::from_str(x_string.as_str()).expect("cannot parse x");
-
-        let y = i64::from_str(y_string.as_str()).expect("cannot parse x");
-
-        (x, y)
-    }).unwrap();
-    (x, y)
+fn parse_pair(string: &str) -> Result<Pos, ParseError> {
+    match ints::<i64>(string)?.as_slice() {
+        [x, y] => Ok(Pos::new(*x, *y)),
+        _ => Err(ParseError::Malformed(string.to_string())),
+    }
 }
 
 impl FromStr for Input {
-    type Err = InputError;
+    type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let button_re: Regex =
-            Regex::new("Button [AB]: X(?<x_sign>[-+])(?<x>\\d+), Y(?<y_sign>[-+])(?<y>\\d+)")
-                .unwrap();
-        let target_re: Regex = Regex::new("Prize: X=(?<x>\\d+), Y=(?<y>\\d+)").unwrap();
-        let quizes = input.split("\n\n").map(|ch| {
+        let quizzes = sections(input).into_iter().map(|ch| {
             let rows: Vec<&str> = ch.lines().collect();
-            let a = parse_button(rows[0], &button_re);
-            let b = parse_button(rows[1], &button_re);
-            let target = parse_target(rows[2], &target_re);
-            Quiz {
-                a: Pos::new(a.0, a.1),
-                b: Pos::new(b.0, b.1),
-                target: Pos::new(target.0, target.1),
+            if rows.len() != 3 {
+                return Err(ParseError::Malformed(ch.to_string()));
             }
-        }).collect();
+            Ok(Quiz {
+                a: parse_pair(rows[0])?,
+                b: parse_pair(rows[1])?,
+                target: parse_pair(rows[2])?,
+            })
+        }).collect::<Result<Vec<Quiz>, _>>()?;
         Ok(Input {
-            quizzes: quizes
+            quizzes
         })
     }
 }
 
-fn part1(input: Input) -> i64 {
+fn part1(input: Input) -> Answer {
     let scale: i64 = 0;
-    calculate(input, scale)
+    calculate(input, scale).into()
 }
 
-fn part2(input: Input) -> i64 {
+fn part2(input: Input) -> Answer {
     let scale: i64 = 10000000000000;
-    calculate(input, scale)
+    calculate(input, scale).into()
 }
 
 fn calculate(input: Input, scale: i64) -> i64 {
     let mut result = 0;
     for quiz in input.quizzes {
-        let mut a = -1;
-        {
-            let top = (quiz.target.x + scale) * quiz.b.y - (quiz.target.y + scale) * quiz.b.x;
-            let bottom = quiz.a.x * quiz.b.y - quiz.a.y * quiz.b.x;
-            if top % bottom == 0 {
-                a = top / bottom;
-            }
-        }
-        let mut b = -1;
-        {
-            let top = (quiz.target.y + scale) * quiz.a.x - (quiz.target.x + scale) * quiz.a.y;
-            let bottom = quiz.a.x * quiz.b.y - quiz.a.y * quiz.b.x;
-            if top % bottom == 0 {
-                b = top / bottom;
-            }
-        }
-        if a != -1 && b != -1 {
+        let target = Pos::new(quiz.target.x + scale, quiz.target.y + scale);
+        if let Some((a, b)) = presses(&quiz.a, &quiz.b, &target) {
             result += a * 3 + b;
         }
     }
     result
 }
 
+fn presses(a: &Pos, b: &Pos, target: &Pos) -> Option<(i64, i64)> {
+    if let Some((na, nb)) = solve_2x2([[a.x, b.x], [a.y, b.y]], [target.x, target.y]) {
+        let (na, nb) = (na.to_integer()?, nb.to_integer()?);
+        return if na >= 0 && nb >= 0 { Some((na, nb)) } else { None };
+    }
+    // buttons are collinear, the prize has to be on the same line
+    if a.x * target.y != a.y * target.x || b.x * target.y != b.y * target.x {
+        return None;
+    }
+    let (na, nb) = if a.x != 0 || b.x != 0 {
+        min_cost_combination(a.x, b.x, target.x, 3, 1)?
+    } else {
+        min_cost_combination(a.y, b.y, target.y, 3, 1)?
+    };
+    if a.y * na + b.y * nb == target.y {
+        Some((na, nb))
+    } else {
+        None
+    }
+}
+
 
 // #[derive(Clone, Eq, PartialEq)]
 // struct State {
@@ -272,4 +246,11 @@ mod tests {
         let res = part2(input());
         assert_eq!(875318608908, res)
     }
+
+    #[test]
+    fn test_collinear() {
+        let input = Input::from_str("Button A: X+4, Y+4\nButton B: X+1, Y+1\nPrize: X=10, Y=10\n").unwrap();
+        let res = part1(input);
+        assert_eq!(8, res)
+    }
 }
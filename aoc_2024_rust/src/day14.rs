@@ -1,4 +1,6 @@
-use regex::Regex;
+use crate::answer::Answer;
+use crate::math::crt;
+use crate::parse::{ints, ParseError};
 use std::fs;
 use std::str::FromStr;
 
@@ -24,9 +26,12 @@ struct Robot {
 }
 
 impl Robot {
-    fn step(&mut self, n: i64) {
-        self.pos.x += self.vel.x * n;
-        self.pos.y += self.vel.y * n;
+    // position after n steps; the x and y coordinates are periodic
+    // modulo the grid size, so n is reduced first and never overflows
+    fn at(&self, n: i64, x_max: i64, y_max: i64) -> (i64, i64) {
+        let x = self.pos.x + self.vel.x * n.rem_euclid(x_max);
+        let y = self.pos.y + self.vel.y * n.rem_euclid(y_max);
+        (x.rem_euclid(x_max), y.rem_euclid(y_max))
     }
 }
 
@@ -35,67 +40,40 @@ struct Input {
     robots: Vec<Robot>,
 }
 
-#[derive(Debug)]
-enum InputError {}
-
-
 impl FromStr for Input {
-    type Err = InputError;
+    type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let mut robots = vec![];
-        let re: Regex = Regex::new("p=(?<x>-?\\d+),(?<y>-?\\d+) v=(?<vx>-?\\d+),(?<vy>-?\\d+)").unwrap();
-        for line in input.lines() {
-            re.captures(line).iter().for_each(|c| {
-                let x = i64::from_str(
-                    c.name("x").expect("cannot parse sign").as_str()
-                ).expect("cannot parse x");
-                let y = i64::from_str(
-                    c.name("y").expect("cannot parse sign").as_str()
-                ).expect("cannot parse y");
-
-                let vx = i64::from_str(
-                    c.name("vx").expect("cannot parse sign").as_str()
-                ).expect("cannot parse vx");
-                let vy = i64::from_str(
-                    c.name("vy").expect("cannot parse sign").as_str()
-                ).expect("cannot parse vy");
-                robots.push(
-                    Robot {
-                        pos: Pos {
-                            x,
-                            y,
-                        },
-                        vel: Pos {
-                            x: vx,
-                            y: vy,
-                        },
-                    }
-                )
-            });
-        };
+        let robots = input.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| match ints::<i64>(line)?.as_slice() {
+                [x, y, vx, vy] => Ok(Robot {
+                    pos: Pos {
+                        x: *x,
+                        y: *y,
+                    },
+                    vel: Pos {
+                        x: *vx,
+                        y: *vy,
+                    },
+                }),
+                _ => Err(ParseError::Malformed(line.to_string())),
+            })
+            .collect::<Result<Vec<Robot>, _>>()?;
         Ok(Input {
             robots
         })
     }
 }
 
-fn part1(input: Input) -> i64 {
+fn part1(input: Input) -> Answer {
     let x_max = 101;
     let y_max = 103;
     let x_mid = x_max / 2;
     let y_mid = y_max / 2;
     let mut qrant = [0; 4];
-    for mut robot in input.robots.clone() {
-        robot.step(100);
-        let mut x = robot.pos.x % x_max;
-        if x < 0 {
-            x += x_max;
-        }
-        let mut y = robot.pos.y % y_max;
-        if y < 0 {
-            y += y_max;
-        }
+    for robot in input.robots.iter() {
+        let (x, y) = robot.at(100, x_max, y_max);
         if x < x_mid / 2 && y < y_mid {
             qrant[0] += 1;
         }
@@ -110,55 +88,33 @@ fn part1(input: Input) -> i64 {
         }
     }
     let res = qrant.iter().sum::<i64>();
-    res
+    res.into()
 }
 
-fn part2(input: Input) -> i64 {
-    let x_max = 101;
-    let y_max = 103;
+// the robots form the tree where they bunch up the most; no robots, no tree
+fn part2(input: Input) -> Answer {
+    clustered(&input, 101, 103).map_or("no tree".into(), Answer::from)
+}
 
-    let mut robots = input.robots.clone();
-    let mut step = 0;
-
-    loop {
-        step += 1;
-        let mut found_tree = true;
-        let mut map = [[false; 101]; 103];
-        for robot in &mut robots {
-            robot.step(1);
-            let mut x = robot.pos.x % x_max;
-            if x < 0 {
-                x += x_max;
-            }
-            let mut y = robot.pos.y % y_max;
-            if y < 0 {
-                y += y_max;
-            }
-            if map[y as usize][x as usize] {
-                found_tree = false;
-            }
-            if found_tree {
-                map[y as usize][x as usize] = true;
-            }
-        }
-        if found_tree {
-            // println!("Num Steps: {}", step);
-            // for y in 0..y_max {
-            //     for x in 0..x_max {
-            //         if map[y as usize][x as usize] {
-            //             print!(".");
-            //         } else {
-            //             print!(" ");
-            //         }
-            //     }
-            //     println!()
-            // }
-
-            break;
-        }
+/// Step at which the robots bunch up the most. Each axis is periodic on its own,
+/// so the steps with the least spread in x and in y are found separately and
+/// combined into one step modulo `lcm(x_max, y_max)` by the Chinese Remainder Theorem.
+fn clustered(input: &Input, x_max: i64, y_max: i64) -> Option<i64> {
+    if input.robots.is_empty() {
+        return None;
     }
-
-    step
+    let n = input.robots.len() as i64;
+    // n^2 times the variance, exact in integers
+    let spread = |values: Vec<i64>| {
+        n * values.iter().map(|v| v * v).sum::<i64>() - values.iter().sum::<i64>().pow(2)
+    };
+    let tx = (0..x_max).min_by_key(|&step| {
+        spread(input.robots.iter().map(|r| r.at(step, x_max, y_max).0).collect())
+    })?;
+    let ty = (0..y_max).min_by_key(|&step| {
+        spread(input.robots.iter().map(|r| r.at(step, x_max, y_max).1).collect())
+    })?;
+    crt(&[(tx, x_max), (ty, y_max)]).map(|(step, _)| step)
 }
 
 
@@ -182,7 +138,25 @@ mod tests {
 
     #[test]
     fn test_part2() {
+        // the example is made for an 11 by 7 grid and draws no tree, this is just where it bunches up
         let res = part2(input());
-        assert_eq!(1, res)
+        assert_eq!(5253, res);
+        assert_eq!("no tree", part2(Input { robots: vec![] }))
+    }
+
+    #[test]
+    fn test_clustered() {
+        // every robot passes (50, 50) at step 4321, which is 79 mod 101 and 98 mod 103
+        let robots = (1..=12).map(|v| Robot {
+            pos: Pos {
+                x: (50 - v * 4321_i64).rem_euclid(101),
+                y: (50 - 3 * v * 4321_i64).rem_euclid(103),
+            },
+            vel: Pos {
+                x: v,
+                y: 3 * v,
+            },
+        }).collect();
+        assert_eq!(Some(4321), clustered(&Input { robots }, 101, 103));
     }
 }
@@ -1,5 +1,7 @@
+use crate::answer::Answer;
 use crate::day15::Dir::*;
 use crate::day15::Item::*;
+use crate::parse::{section_pair, ParseError};
 use std::collections::{HashSet, VecDeque};
 use std::fs;
 use std::str::FromStr;
@@ -79,9 +81,6 @@ struct Input {
     robot: Pos,
 }
 
-#[derive(Debug)]
-enum InputError {}
-
 #[derive(Clone, Eq, PartialEq)]
 enum Item {
     Wall,
@@ -100,12 +99,12 @@ enum Dir {
 }
 
 impl FromStr for Input {
-    type Err = InputError;
+    type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = input.split("\n\n").collect();
+        let (map_str, actions_str) = section_pair(input)?;
         let mut robot: Pos = Pos { x: 0, y: 0 };
-        let map: Vec<Vec<Item>> = parts[0].lines().enumerate().map(|(y, line)| {
+        let map: Vec<Vec<Item>> = map_str.lines().enumerate().map(|(y, line)| {
             line.chars().enumerate().map(|(x, c)| {
                 let item = match c {
                     '#' => Item::Wall,
@@ -118,23 +117,21 @@ impl FromStr for Input {
                     }
                     'O' => Item::Box,
                     '.' => Item::Free,
-                    _ => panic!("Unknown character {}", c)
+                    _ => return Err(ParseError::Malformed(line.to_string())),
                 };
-                item
+                Ok(item)
             }).collect()
-        }).collect();
+        }).collect::<Result<_, _>>()?;
 
-        let actions: Vec<Dir> = parts[1].chars().filter(|v| {
-            *v != '\n'
-        }).map(|v| {
+        let actions: Vec<Dir> = actions_str.lines().flat_map(|l| l.chars()).map(|v| {
             match v {
-                '<' => Left,
-                '>' => Right,
-                '^' => Up,
-                'v' => Down,
-                _ => panic!("unknown direction")
+                '<' => Ok(Left),
+                '>' => Ok(Right),
+                '^' => Ok(Up),
+                'v' => Ok(Down),
+                _ => Err(ParseError::Malformed(v.to_string())),
             }
-        }).collect();
+        }).collect::<Result<_, _>>()?;
         Ok(Input {
             map,
             actions,
@@ -143,7 +140,7 @@ impl FromStr for Input {
     }
 }
 
-fn part1(input: Input) -> i64 {
+fn part1(input: Input) -> Answer {
     let mut store = input.map;
 
 
@@ -169,14 +166,14 @@ fn part1(input: Input) -> i64 {
         // }
     }
     let mut res = 0;
-    for y in 0..store.len() {
-        for x in 0..store[0].len() {
-            if store[y][x] == Box {
+    for (y, row) in store.iter().enumerate() {
+        for (x, item) in row.iter().enumerate() {
+            if *item == Box {
                 res += 100 * y as i64 + x as i64;
             }
         }
     }
-    res
+    res.into()
 }
 
 fn try_push(pos: &Pos, dir: &Dir, store: &mut Store) -> bool {
@@ -198,7 +195,7 @@ fn try_push(pos: &Pos, dir: &Dir, store: &mut Store) -> bool {
     }
 }
 
-fn part2(input: Input) -> i64 {
+fn part2(input: Input) -> Answer {
     let mut store: Store = input.map.iter().map(|l| {
         l.iter().flat_map(|i| {
             if *i == Box {
@@ -232,7 +229,7 @@ fn part2(input: Input) -> i64 {
 
     for dir in input.actions {
         let next = robot.step(&dir);
-        if let Some(path) = check_push(&next, &dir, &mut store) {
+        if let Some(path) = check_push(&next, &dir, &store) {
             path.iter().rev().for_each(|pos| {
                 let new_pos = pos.step(&dir);
                 let item = store[pos.yu()][pos.xu()].clone();
@@ -260,15 +257,15 @@ fn part2(input: Input) -> i64 {
         // }
     }
     let mut res = 0;
-    for y in 0..store.len() {
-        for x in 0..store[0].len() {
-            if store[y][x] == L {
+    for (y, row) in store.iter().enumerate() {
+        for (x, item) in row.iter().enumerate() {
+            if *item == L {
                 res += 100 * y as i64 + x as i64;
             }
         }
     }
 
-    res
+    res.into()
 }
 
 fn check_push(pos: &Pos, dir: &Dir, store: &Store) -> Option<Vec<Pos>> {
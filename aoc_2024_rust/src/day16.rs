@@ -1,3 +1,4 @@
+use crate::answer::Answer;
 use crate::day16::Dir::*;
 use std::cmp::Reverse;
 use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
@@ -95,7 +96,7 @@ impl FromStr for Input {
     }
 }
 
-fn part1(input: Input) -> i64 {
+fn part1(input: Input) -> Answer {
     let map = input.map;
     let deer = Deer {
         y: map.len() - 2,
@@ -165,11 +166,11 @@ fn part1(input: Input) -> i64 {
             queue.push(Reverse((new_score + distance as i64, id)))
         }
     }
-    best_score
+    best_score.into()
 }
 
 
-fn part2(input: Input) -> i64 {
+fn part2(input: Input) -> Answer {
     let map = input.map;
     let deer = Deer {
         y: map.len() - 2,
@@ -255,7 +256,7 @@ fn part2(input: Input) -> i64 {
         }
     }
 
-    visited_tiles.len() as i64
+    visited_tiles.len().into()
 }
 
 
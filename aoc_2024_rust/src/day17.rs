@@ -1,4 +1,6 @@
+use crate::answer::Answer;
 use crate::day17::OpCode::*;
+use crate::parse::{ints, section_pair, ParseError};
 use std::fs;
 use std::str::FromStr;
 
@@ -63,9 +65,9 @@ impl Executor {
             match (op_code, param) {
                 (Adv, Some(op)) => {
                     let denom: u64 = u64::pow(2, self.get_combo_op(op) as u32);
-                    self.a = self.a / denom;
+                    self.a /= denom;
                 }
-                (Bxl, Some(op)) => self.b = self.b ^ self.get_op(op),
+                (Bxl, Some(op)) => self.b ^= self.get_op(op),
                 (Bst, Some(op)) => self.b = self.get_combo_op(op) % 8,
                 (Jnz, Some(op)) => {
                     if self.a != 0 {
@@ -75,7 +77,7 @@ impl Executor {
                         // do not jump
                     }
                 }
-                (Bxc, _) => self.b = self.b ^ self.c,
+                (Bxc, _) => self.b ^= self.c,
                 (Out, Some(op)) => {
                     let value = (self.get_combo_op(op) % 8) as u8;
                     output.push(value);
@@ -125,52 +127,45 @@ impl OpCode {
     }
 }
 
-#[derive(Debug)]
-enum InputError {}
-
 impl FromStr for Executor {
-    type Err = InputError;
+    type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = input.split("\n\n").collect();
-        let registers = parts[0]
-            .lines()
-            .map(|l| {
-                u64::from_str(l.split(": ").last().expect("cannot extract string"))
-                    .expect("cannot extract value")
-            })
-            .collect::<Vec<u64>>();
-        let program = parts[1]
+        let (registers_str, program_str) = section_pair(input)?;
+        let registers = registers_str
             .lines()
-            .flat_map(|l| {
-                l.trim().split_at(9).1.split(",").map(|v| {
-                    // println!("Code '{}'", v);
-                    u8::from_str(v).expect("cannot parse opcode")
-                })
+            .map(|l| match ints::<u64>(l)?.as_slice() {
+                [value] => Ok(*value),
+                _ => Err(ParseError::Malformed(l.to_string())),
             })
-            .collect::<Vec<u8>>();
+            .collect::<Result<Vec<u64>, _>>()?;
+        if registers.len() != 3 {
+            return Err(ParseError::Malformed(registers_str.to_string()));
+        }
+        let program = ints::<u8>(program_str)?;
         Ok(Executor {
             a: registers[0],
             b: registers[1],
             c: registers[2],
-            program: program,
+            program,
         })
     }
 }
 
-fn part1(input: &mut Executor) -> String {
+fn part1(input: &mut Executor) -> Answer {
     input
         .execute()
         .iter()
         .map(|v| v.to_string())
         .collect::<Vec<String>>()
         .join(",")
+        .into()
 }
 
-fn part2(executor: &mut Executor) -> u64 {
+fn part2(executor: &mut Executor) -> Answer {
     for start in 0..u64::pow(2, 7) {
         if let Some(a) = match_recursive(0, start, executor) {
-            return a;
+            return a.into();
         }
     }
     panic!("Not found")
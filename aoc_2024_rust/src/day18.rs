@@ -1,4 +1,7 @@
 use crate::day18::Dir::*;
+use crate::answer::Answer;
+use crate::parse::{list, ParseError};
+use crate::union_find::UnionFind;
 use std::cmp::{Ordering, Reverse};
 use std::collections::BinaryHeap;
 use std::fmt::{Display, Formatter};
@@ -12,7 +15,7 @@ pub fn print() {
 
     println!("Day 18");
     println!("Part 1 {}", part1(input.clone(), 1024, 70));
-    println!("Part 2 {}", part2(input.clone(), 70).unwrap());
+    println!("Part 2 {}", part2(input.clone(), 70));
 }
 
 #[derive(Clone)]
@@ -81,29 +84,24 @@ impl PartialOrd for Pos {
     }
 }
 
-#[derive(Debug)]
-enum InputError {}
-
 impl FromStr for Input {
-    type Err = InputError;
+    type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
         let blocks = input
             .lines()
+            .filter(|l| !l.trim().is_empty())
             .enumerate()
-            .map(|(z, l)| {
-                let coords: Vec<&str> = l.split(",").collect();
-                let x = i32::from_str(coords[0]).expect("cannot parse x");
-                let y = i32::from_str(coords[1]).expect("cannot parse y");
-                let pos = Pos { x, y };
-                (pos, (z + 1) as u64)
+            .map(|(z, l)| match list::<i32>(l, ',')?.as_slice() {
+                [x, y] => Ok((Pos { x: *x, y: *y }, (z + 1) as u64)),
+                _ => Err(ParseError::Malformed(l.to_string())),
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
         Ok(Input { blocks })
     }
 }
 
-fn part1(input: Input, steps: u64, grid_size: i32) -> i64 {
+fn part1(input: Input, steps: u64, grid_size: i32) -> Answer {
     let x_max = grid_size;
     let y_max = grid_size;
     let start = Pos { x: 0, y: 0 };
@@ -114,174 +112,66 @@ fn part1(input: Input, steps: u64, grid_size: i32) -> i64 {
         map[b.y as usize][b.x as usize] = Some(*ts);
     });
     if let Some((min_path, _)) = find_path(&start, &target, &map, steps, grid_size, grid_size) {
-        min_path as i64
+        min_path.into()
     } else {
         panic!("cannot find path!!!")
     }
 }
 
-fn part2(input: Input, grid_size: i32) -> Option<Pos> {
-    let x_max = grid_size;
-    let y_max = grid_size;
+fn part2(input: Input, grid_size: i32) -> Answer {
+    let block = first_blocking(input, grid_size).expect("exit is never blocked");
+    Answer::Coord(block.x as i64, block.y as i64)
+}
+
+// Drops every byte first, then lifts them in reverse order joining free cells.
+// The byte whose removal connects start and target is the first one that blocks it.
+fn first_blocking(input: Input, grid_size: i32) -> Option<Pos> {
+    let size = (grid_size + 1) as usize;
+    let idx = |pos: &Pos| pos.y as usize * size + pos.x as usize;
     let start = Pos { x: 0, y: 0 };
-    let target = Pos { x: x_max, y: y_max };
+    let target = Pos { x: grid_size, y: grid_size };
 
-    let blocks = input.blocks;
-    // let map = input.map;
-    let mut map: Vec<Vec<Option<u64>>> =
-        vec![vec![None; (x_max + 1) as usize]; (y_max + 1) as usize];
-    blocks.iter().for_each(|(b, ts)| {
-        map[b.y as usize][b.x as usize] = Some(*ts);
+    let mut blocked = vec![vec![false; size]; size];
+    input.blocks.iter().for_each(|(b, _)| {
+        blocked[b.y as usize][b.x as usize] = true;
     });
 
-    {
-        let mut last_path = vec![];
-        for (block, blocks_ts) in blocks {
-            if !last_path.is_empty() && !last_path.contains(&block) {
+    let mut cells = UnionFind::new(size * size);
+    for y in 0..=grid_size {
+        for x in 0..=grid_size {
+            let pos = Pos { x, y };
+            if blocked[y as usize][x as usize] {
                 continue;
             }
-
-            if let Some((_min_len, min_path)) =
-                find_path(&start, &target, &map, blocks_ts, grid_size, grid_size)
-            {
-                last_path = min_path;
-            } else {
-                return Some(block);
+            for next in pos.next(grid_size, grid_size) {
+                if !blocked[next.y as usize][next.x as usize] {
+                    cells.union(idx(&pos), idx(&next));
+                }
             }
         }
-        None
+    }
+    if cells.connected(idx(&start), idx(&target)) {
+        return None;
     }
 
-    // {
-    //     let mut block_idx = 0;
-    //     let mut blocks_ts = &blocks[block_idx].1;
-    //
-    //     let mut states: Vec<(u64, Pos, Vec<Pos>)> = vec![];
-    //
-    //     states.push((0, start.clone(), vec![start.clone()]));
-    //     let mut queue: BinaryHeap<Reverse<(u64, usize)>> = BinaryHeap::new();
-    //
-    //     queue.push(Reverse((grid_size as u64 * 2, 0)));
-    //
-    //     let mut backlog: Vec<Reverse<(u64, usize)>> = vec![];
-    //     let mut visited: HashMap<Pos, u64> = HashMap::new();
-    //
-    //     while let Some(Reverse((score, idx))) = queue.pop() {
-    //         let next_positions = {
-    //             let (curr_ts, curr, path) = &states[idx];
-    //             if let Some(&block_ts) = map.get(&curr) {
-    //                 if block_ts <= *blocks_ts {
-    //                     continue;
-    //                 }
-    //             }
-    //             // println!("[{}] {:?}", curr_ts, curr);
-    //             // for y in 0..=y_max {
-    //             //     for x in 0..=x_max {
-    //             //         let cell = Pos { x, y };
-    //             //         if let Some(&block) = map.get(&cell) {
-    //             //             if block <= *blocks_ts {
-    //             //                 print!("#");
-    //             //                 continue;
-    //             //             }
-    //             //         }
-    //             //         if path.contains(&cell) {
-    //             //             print!("O");
-    //             //         } else {
-    //             //             print!(".");
-    //             //         }
-    //             //     }
-    //             //     println!();
-    //             // }
-    //             if *curr == target {
-    //                 // println!("{:?}", path);
-    //                 // path.iter().all(|p| {
-    //                 //     map.get(p)
-    //                 //         .filter(|&block_ts| block_ts <= blocks_ts)
-    //                 //         .is_none()
-    //                 // })
-    //                 let mut block = &blocks[block_idx].0;
-    //                 while !path.contains(block) {
-    //                     block_idx += 1;
-    //                     if block_idx >= blocks.len() {
-    //                         return None;
-    //                     }
-    //                     block = &blocks[block_idx].0;
-    //                     blocks_ts = &blocks[block_idx].1;
-    //                 }
-    //                 visited = HashMap::new();
-    //                 // for step in path.iter().rev() {
-    //                 //     visited.remove(step);
-    //                 //     if step == block {
-    //                 //         // println!("step cleaned");
-    //                 //         break;
-    //                 //     }
-    //                 // }
-    //                 while let Some(Reverse((score, idx))) = backlog.pop() {
-    //                     let (_, _, path) = &states[idx];
-    //                     let is_blocked = path.iter().all(|p| {
-    //                         map.get(p)
-    //                             .filter(|&block_ts| block_ts <= blocks_ts)
-    //                             .is_none()
-    //                     });
-    //                     if !is_blocked {
-    //                         queue.push(Reverse((score, idx)));
-    //                     }
-    //                 }
-    //                 // println!("found number");
-    //                 continue;
-    //             }
-    //
-    //             if let Some(last) = visited.get_mut(&curr) {
-    //                 if *last <= *curr_ts {
-    //                     backlog.push(Reverse((score, idx)));
-    //                     continue;
-    //                 } else {
-    //                     *last = *curr_ts;
-    //                 }
-    //             } else {
-    //                 visited.insert(curr.clone(), *curr_ts);
-    //             }
-    //             let next: Vec<Pos> = curr
-    //                 .next(x_max, y_max)
-    //                 .into_iter()
-    //                 .filter(|p| !path.contains(p))
-    //                 .collect();
-    //
-    //             (*curr_ts, next, path.clone())
-    //         };
-    //
-    //         for next in next_positions.1 {
-    //             let next_ts = next_positions.0 + 1;
-    //             if next_positions.2.contains(&next) {
-    //                 continue;
-    //             }
-    //             if let Some(&block_ts) = map.get(&next) {
-    //                 if block_ts <= *blocks_ts {
-    //                     continue;
-    //                 }
-    //             }
-    //             let score = (x_max - &next.x + y_max - &next.y) as u64 + next_ts;
-    //             let mut new_path = next_positions.2.clone();
-    //             let idx = states.len();
-    //             new_path.push(next.clone());
-    //             states.push((next_ts, next.clone(), new_path));
-    //             if let Some(&last) = visited.get(&next) {
-    //                 if last <= next_ts {
-    //                     backlog.push(Reverse((score, idx)));
-    //                     continue;
-    //                 }
-    //             }
-    //             queue.push(Reverse((score, idx)))
-    //         }
-    //     }
-    //     Some(blocks[block_idx].0.clone())
-    // }
+    for (block, _) in input.blocks.iter().rev() {
+        blocked[block.y as usize][block.x as usize] = false;
+        for next in block.next(grid_size, grid_size) {
+            if !blocked[next.y as usize][next.x as usize] {
+                cells.union(idx(block), idx(&next));
+            }
+        }
+        if cells.connected(idx(&start), idx(&target)) {
+            return Some(block.clone());
+        }
+    }
+    None
 }
 
 fn find_path(
     start: &Pos,
     target: &Pos,
-    blocks: &Vec<Vec<Option<u64>>>,
+    blocks: &[Vec<Option<u64>>],
     blocks_ts: u64,
     x_max: i32,
     y_max: i32,
@@ -372,6 +262,6 @@ mod tests {
     #[test]
     fn test_part2() {
         let res = part2(input(), 6);
-        assert_eq!(Some(Pos { x: 6, y: 1 }), res)
+        assert_eq!(Answer::Coord(6, 1), res)
     }
 }
@@ -1,4 +1,6 @@
-use std::collections::{HashMap, HashSet};
+use crate::answer::Answer;
+use crate::parse::{list, section_pair, ParseError};
+use crate::memo::Memo;
 use std::fs;
 use std::str::FromStr;
 
@@ -17,16 +19,13 @@ struct Input {
     patterns: Vec<String>,
 }
 
-#[derive(Debug)]
-enum InputError {}
-
 impl FromStr for Input {
-    type Err = InputError;
+    type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = input.split("\n\n").collect();
-        let towels: Vec<String> = parts[0].split(", ").map(|s| s.to_string()).collect();
-        let patterns: Vec<String> = parts[1].lines().map(|s| s.to_string()).collect();
+        let (towels_str, patterns_str) = section_pair(input)?;
+        let towels: Vec<String> = list(towels_str, ',')?;
+        let patterns: Vec<String> = patterns_str.lines().map(|s| s.trim().to_string()).collect();
 
         Ok(Input {
             towels,
@@ -35,56 +34,55 @@ impl FromStr for Input {
     }
 }
 
-fn part1(input: Input) -> i64 {
+fn part1(input: Input) -> Answer {
     let mut num_towels = 0;
-    let mut fails = HashSet::new();
-    input.patterns.into_iter().for_each(|p| {
+    let mut fails = Memo::new();
+    input.patterns.iter().for_each(|p| {
         let contained_towels = input.towels.clone().into_iter().filter(|t| {
             p.contains(t)
         }).collect::<Vec<_>>();
 
-        if rec_match(&p, &contained_towels, &mut fails) {
+        if rec_match(p, &contained_towels, &mut fails) {
             num_towels += 1;
         };
     });
-    num_towels
+    num_towels.into()
 }
 
-fn rec_match(pattern: &str, towels: &Vec<String>, fails: &mut HashSet<String>) -> bool {
+fn rec_match<'a>(pattern: &'a str, towels: &[String], fails: &mut Memo<&'a str, bool>) -> bool {
     if pattern.is_empty() {
         return true;
     }
-    if fails.contains(pattern) {
-        return false;
+    if let Some(matched) = fails.get(pattern) {
+        return matched;
     }
     for towel in towels.iter() {
         if pattern.starts_with(towel) && rec_match(&pattern[towel.len()..], towels, fails) {
             return true;
         }
     }
-    fails.insert(pattern.to_string());
-    false
+    fails.insert(pattern, false)
 }
 
-fn part2(input: Input) -> i64 {
+fn part2(input: Input) -> Answer {
     let mut num_towels = 0;
-    let mut patterns = HashMap::new();
-    input.patterns.into_iter().for_each(|p| {
+    let mut patterns = Memo::new();
+    input.patterns.iter().for_each(|p| {
         let contained_towels = input.towels.clone().into_iter().filter(|t| {
             p.contains(t)
         }).collect::<Vec<_>>();
-        
-        num_towels += rec_match_count(&p, &contained_towels, &mut patterns);
+
+        num_towels += rec_match_count(p, &contained_towels, &mut patterns);
     });
-    num_towels
+    num_towels.into()
 }
 
-fn rec_match_count(pattern: &str, towels: &Vec<String>, patterns: &mut HashMap<String, i64>) -> i64 {
+fn rec_match_count<'a>(pattern: &'a str, towels: &[String], patterns: &mut Memo<&'a str, i64>) -> i64 {
     if pattern.is_empty() {
         return 1;
     }
     if let Some(n) = patterns.get(pattern) {
-        return *n;
+        return n;
     }
     let mut n = 0;
     for towel in towels.iter() {
@@ -92,9 +90,7 @@ fn rec_match_count(pattern: &str, towels: &Vec<String>, patterns: &mut HashMap<S
             n += rec_match_count(&pattern[towel.len()..], towels, patterns)
         }
     }
-    patterns.insert(pattern.to_string(), n);
-
-    n
+    patterns.insert(pattern, n)
 }
 
 #[cfg(test)]
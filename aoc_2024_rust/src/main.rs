@@ -17,25 +17,41 @@ mod day16;
 mod day17;
 mod day18;
 mod day19;
+mod answer;
+mod cli;
+mod math;
+mod memo;
+mod parse;
+mod render;
+mod rule_graph;
+mod union_find;
 
 fn main() {
-    day01::print();
-    day02::print();
-    day03::print();
-    day04::print();
-    day05::print();
-    day06::print();
-    day07::print();
-    day08::print();
-    day09::print();
-    day10::print();
-    day11::print();
-    day12::print();
-    day13::print();
-    day14::print();
-    day15::print();
-    day16::print();
-    day17::print();
-    day18::print();
-    day19::print();
+    let days: [fn(); 19] = [
+        day01::print,
+        day02::print,
+        day03::print,
+        day04::print,
+        day05::print,
+        day06::print,
+        day07::print,
+        day08::print,
+        day09::print,
+        day10::print,
+        day11::print,
+        day12::print,
+        day13::print,
+        day14::print,
+        day15::print,
+        day16::print,
+        day17::print,
+        day18::print,
+        day19::print,
+    ];
+    let selected = cli::day();
+    for (idx, print) in days.iter().enumerate() {
+        if selected.is_none_or(|day| day == idx + 1) {
+            print();
+        }
+    }
 }
@@ -0,0 +1,209 @@
+use std::fmt::{Display, Formatter};
+
+/// Greatest common divisor, always non-negative. `gcd(0, 0)` is 0.
+pub fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a as i64
+}
+
+pub fn lcm(a: i64, b: i64) -> i64 {
+    checked_lcm(a, b).expect("lcm overflow")
+}
+
+pub fn checked_lcm(a: i64, b: i64) -> Option<i64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b).map(|v| v.abs())
+}
+
+/// Extended Euclid: returns `(g, x, y)` with `a * x + b * y == g` and `g == gcd(a, b)`.
+pub fn ext_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// Inverse of `a` modulo `m` in `0..m`, if `a` and `m` are coprime.
+pub fn mod_inv(a: i64, m: i64) -> Option<i64> {
+    let (g, x, _) = ext_gcd(a.rem_euclid(m), m);
+    if g == 1 {
+        Some(x.rem_euclid(m))
+    } else {
+        None
+    }
+}
+
+/// Chinese Remainder Theorem over `(residue, modulus)` pairs.
+/// Moduli don't have to be coprime. Returns `(r, m)` such that every
+/// solution is `r + k * m`, or `None` if the system is inconsistent
+/// or the combined modulus doesn't fit into `i64`.
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    let mut r: i128 = 0;
+    let mut m: i128 = 1;
+    for &(ri, mi) in congruences {
+        let mi = mi as i128;
+        let ri = (ri as i128).rem_euclid(mi);
+        let g = gcd(m as i64, mi as i64) as i128;
+        if (ri - r) % g != 0 {
+            return None;
+        }
+        let step = mi / g;
+        let inv = mod_inv((m / g % step) as i64, step as i64)? as i128;
+        let k = ((ri - r) / g % step * inv).rem_euclid(step);
+        r += m * k;
+        m = m.checked_mul(step)?;
+        if m > i64::MAX as i128 {
+            return None;
+        }
+        r = r.rem_euclid(m);
+    }
+    Some((r as i64, m as i64))
+}
+
+/// Exact fraction, kept with a positive denominator and reduced.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Rational {
+    pub num: i128,
+    pub den: i128,
+}
+
+impl Rational {
+    pub fn new(num: i128, den: i128) -> Self {
+        assert_ne!(den, 0, "zero denominator");
+        let sign = den.signum();
+        let g = gcd_i128(num, den);
+        Rational {
+            num: sign * num / g,
+            den: sign * den / g,
+        }
+    }
+
+    pub fn to_integer(self) -> Option<i64> {
+        if self.den == 1 {
+            i64::try_from(self.num).ok()
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+fn gcd_i128(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1) as i128
+}
+
+/// Solves `m * [x, y] = rhs` exactly with Cramer's rule.
+/// `None` if the matrix is singular or the determinant overflows.
+pub fn solve_2x2(m: [[i64; 2]; 2], rhs: [i64; 2]) -> Option<(Rational, Rational)> {
+    let [[a, b], [c, d]] = m.map(|row| row.map(|v| v as i128));
+    let [e, f] = rhs.map(|v| v as i128);
+    let det = (a * d).checked_sub(b * c)?;
+    if det == 0 {
+        return None;
+    }
+    let x = (e * d).checked_sub(b * f)?;
+    let y = (a * f).checked_sub(e * c)?;
+    Some((Rational::new(x, det), Rational::new(y, det)))
+}
+
+/// Non-negative integer solution of `a * x + b * y == t` with `a, b > 0`
+/// that minimises `x * cost_x + y * cost_y`.
+pub fn min_cost_combination(a: i64, b: i64, t: i64, cost_x: i64, cost_y: i64) -> Option<(i64, i64)> {
+    if a <= 0 || b <= 0 || t < 0 {
+        return None;
+    }
+    let (g, x0, y0) = ext_gcd(a, b);
+    if t % g != 0 {
+        return None;
+    }
+    let scale = t / g;
+    let x0 = (x0 as i128) * scale as i128;
+    let y0 = (y0 as i128) * scale as i128;
+    // x = x0 + k * b / g, y = y0 - k * a / g
+    let (dx, dy) = ((b / g) as i128, (a / g) as i128);
+    let k_min = (-x0).div_euclid(dx) + i128::from((-x0).rem_euclid(dx) != 0);
+    let k_max = y0.div_euclid(dy);
+    if k_min > k_max {
+        return None;
+    }
+    // cost is linear in k, so the best solution is on one of the ends
+    let slope = cost_x as i128 * dx - cost_y as i128 * dy;
+    let k = if slope >= 0 { k_min } else { k_max };
+    let x = i64::try_from(x0 + k * dx).ok()?;
+    let y = i64::try_from(y0 - k * dy).ok()?;
+    Some((x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(6, gcd(12, -18));
+        assert_eq!(5, gcd(0, 5));
+        assert_eq!(0, gcd(0, 0));
+        assert_eq!(10403, lcm(101, 103));
+        assert_eq!(None, checked_lcm(i64::MAX, i64::MAX - 1));
+    }
+
+    #[test]
+    fn test_ext_gcd() {
+        let (g, x, y) = ext_gcd(240, 46);
+        assert_eq!(2, g);
+        assert_eq!(2, 240 * x + 46 * y);
+        assert_eq!(Some(4), mod_inv(3, 11));
+        assert_eq!(None, mod_inv(4, 8));
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(Some((23, 105)), crt(&[(2, 3), (3, 5), (2, 7)]));
+        assert_eq!(Some((10, 12)), crt(&[(4, 6), (2, 4)]));
+        assert_eq!(None, crt(&[(1, 4), (2, 6)]));
+    }
+
+    #[test]
+    fn test_solve_2x2() {
+        let (a, b) = solve_2x2([[94, 22], [34, 67]], [8400, 5400]).unwrap();
+        assert_eq!((Some(80), Some(40)), (a.to_integer(), b.to_integer()));
+        let (a, _) = solve_2x2([[2, 0], [0, 1]], [3, 1]).unwrap();
+        assert_eq!("3/2", a.to_string());
+        assert_eq!(None, solve_2x2([[1, 2], [2, 4]], [3, 6]));
+    }
+
+    #[test]
+    fn test_min_cost_combination() {
+        assert_eq!(Some((0, 5)), min_cost_combination(2, 4, 20, 3, 1));
+        assert_eq!(Some((10, 0)), min_cost_combination(2, 4, 20, 1, 3));
+        assert_eq!(None, min_cost_combination(2, 4, 7, 1, 1));
+    }
+}
@@ -0,0 +1,68 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Cache for memoised recursion.
+///
+/// Lookups go through `Borrow`, so a `Memo<String, _>` can be queried with a `&str`,
+/// and keys can themselves be borrowed (`Memo<&str, _>`) to avoid allocating per call.
+pub struct Memo<K, V> {
+    cache: HashMap<K, V>,
+}
+
+impl<K: Hash + Eq, V: Clone> Memo<K, V> {
+    pub fn new() -> Self {
+        Memo {
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn get<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.cache.get(key).cloned()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> V {
+        self.cache.insert(key, value.clone());
+        value
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> Default for Memo<K, V> {
+    fn default() -> Self {
+        Memo::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fib(n: u64, memo: &mut Memo<u64, u64>) -> u64 {
+        if n < 2 {
+            return n;
+        }
+        if let Some(v) = memo.get(&n) {
+            return v;
+        }
+        let v = fib(n - 1, memo) + fib(n - 2, memo);
+        memo.insert(n, v)
+    }
+
+    #[test]
+    fn test_memo() {
+        let mut memo = Memo::new();
+        assert_eq!(12586269025, fib(50, &mut memo));
+    }
+
+    #[test]
+    fn test_borrowed_lookup() {
+        let mut memo: Memo<String, i64> = Memo::new();
+        memo.insert("brwrr".to_string(), 2);
+        assert_eq!(Some(2), memo.get("brwrr"));
+        assert_eq!(None, memo.get("bggr"));
+    }
+}
@@ -0,0 +1,175 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseError {
+    Number(String),
+    Digit { line: usize, column: usize, found: char },
+    Sections { expected: usize, found: usize },
+    Malformed(String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Number(token) => write!(f, "cannot parse number '{}'", token),
+            ParseError::Digit { line, column, found } => {
+                write!(f, "expected digit at {}:{}, found '{}'", line + 1, column + 1, found)
+            }
+            ParseError::Sections { expected, found } => {
+                write!(f, "expected {} sections, found {}", expected, found)
+            }
+            ParseError::Malformed(line) => write!(f, "malformed input '{}'", line),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Splits the input into blocks separated by blank lines.
+/// Accepts `\r\n` line endings and ignores leading and trailing blank lines.
+pub fn sections(input: &str) -> Vec<&str> {
+    let mut sections = vec![];
+    let mut start = None;
+    let mut end = 0;
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            if let Some(start) = start.take() {
+                sections.push(&input[start..end]);
+            }
+        } else {
+            start.get_or_insert(offset);
+            end = offset + line.trim_end_matches(['\r', '\n']).len();
+        }
+        offset += line.len();
+    }
+    if let Some(start) = start {
+        sections.push(&input[start..end]);
+    }
+    sections
+}
+
+/// Same as `sections`, but the input must have exactly two blocks.
+pub fn section_pair(input: &str) -> Result<(&str, &str), ParseError> {
+    match sections(input).as_slice() {
+        [first, second] => Ok((first, second)),
+        other => Err(ParseError::Sections {
+            expected: 2,
+            found: other.len(),
+        }),
+    }
+}
+
+pub fn number<T: FromStr>(token: &str) -> Result<T, ParseError> {
+    T::from_str(token).map_err(|_| ParseError::Number(token.to_string()))
+}
+
+/// Extracts every integer in the line, ignoring anything in between.
+/// A `-` directly in front of the digits makes the value negative.
+pub fn ints<T: FromStr>(line: &str) -> Result<Vec<T>, ParseError> {
+    let bytes = line.as_bytes();
+    let mut values = vec![];
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if !bytes[idx].is_ascii_digit() {
+            idx += 1;
+            continue;
+        }
+        let start = if idx > 0 && bytes[idx - 1] == b'-' {
+            idx - 1
+        } else {
+            idx
+        };
+        while idx < bytes.len() && bytes[idx].is_ascii_digit() {
+            idx += 1;
+        }
+        values.push(number(&line[start..idx])?);
+    }
+    Ok(values)
+}
+
+/// Parses a separated list of values, e.g. `75,47,61` or `0 1 10 99`.
+/// Items are trimmed; with a whitespace separator any run of whitespace splits.
+pub fn list<T: FromStr>(line: &str, sep: char) -> Result<Vec<T>, ParseError> {
+    let line = line.trim();
+    if line.is_empty() {
+        Ok(vec![])
+    } else if sep.is_whitespace() {
+        line.split_whitespace().map(number).collect()
+    } else {
+        line.split(sep).map(|v| number(v.trim())).collect()
+    }
+}
+
+pub fn digits(line: &str) -> Result<Vec<u8>, ParseError> {
+    digit_row(0, line.trim())
+}
+
+pub fn digit_grid(input: &str) -> Result<Vec<Vec<u8>>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(x, line)| digit_row(x, line.trim_end()))
+        .collect()
+}
+
+fn digit_row(x: usize, line: &str) -> Result<Vec<u8>, ParseError> {
+    line.chars()
+        .enumerate()
+        .map(|(y, c)| {
+            c.to_digit(10).map(|d| d as u8).ok_or(ParseError::Digit {
+                line: x,
+                column: y,
+                found: c,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections() {
+        let input = "a\r\nb\r\n\r\n\r\nc\n\n\n";
+        assert_eq!(vec!["a\r\nb", "c"], sections(input));
+        assert_eq!(Ok(("a\r\nb", "c")), section_pair(input));
+        assert_eq!(
+            Err(ParseError::Sections { expected: 2, found: 1 }),
+            section_pair("a\nb\n")
+        );
+    }
+
+    #[test]
+    fn test_ints() {
+        assert_eq!(Ok(vec![0, 4, 3, -3]), ints::<i64>("p=0,4 v=3,-3"));
+        assert_eq!(Ok(vec![94, 34]), ints::<i64>("Button A: X+94, Y+34"));
+        assert_eq!(
+            Err(ParseError::Number("-3".to_string())),
+            ints::<u64>("v=-3")
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        assert_eq!(Ok(vec![75, 47, 61]), list::<u8>("75,47,61\r", ','));
+        assert_eq!(Ok(vec![0, 1, 10]), list::<u64>("0  1 10", ' '));
+        assert_eq!(
+            Ok(vec!["r".to_string(), "wr".to_string()]),
+            list::<String>("r, wr", ',')
+        );
+        assert_eq!(Err(ParseError::Number("".to_string())), list::<u8>("1,,2", ','));
+    }
+
+    #[test]
+    fn test_digit_grid() {
+        assert_eq!(Ok(vec![vec![0, 1], vec![2, 3]]), digit_grid("01\r\n23\r\n"));
+        assert_eq!(
+            Err(ParseError::Digit { line: 1, column: 0, found: '.' }),
+            digit_grid("01\n.3\n")
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use std::fs;
+use std::io;
+
+/// Binary PPM image of a character grid, every cell a `scale` pixel square
+/// coloured by `colour`. PPM needs no image library and most viewers open it.
+pub fn ppm(grid: &[Vec<char>], scale: usize, colour: impl Fn(char) -> [u8; 3]) -> Vec<u8> {
+    let height = grid.len() * scale;
+    let width = grid.first().map_or(0, |row| row.len()) * scale;
+    let mut out = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for row in grid {
+        let line = row.iter()
+            .flat_map(|&c| std::iter::repeat_n(colour(c), scale))
+            .flatten()
+            .collect::<Vec<u8>>();
+        for _ in 0..scale {
+            out.extend_from_slice(&line);
+        }
+    }
+    out
+}
+
+pub fn write_ppm(path: &str, grid: &[Vec<char>], scale: usize, colour: impl Fn(char) -> [u8; 3]) -> io::Result<()> {
+    fs::write(path, ppm(grid, scale, colour))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ppm() {
+        let grid = vec![vec!['#', '.']];
+        let image = ppm(&grid, 2, |c| if c == '#' { [255, 0, 0] } else { [0, 0, 0] });
+        let header = b"P6\n4 2\n255\n";
+        assert_eq!(header, &image[..header.len()]);
+        assert_eq!(header.len() + 4 * 2 * 3, image.len());
+        assert_eq!([255, 0, 0, 255, 0, 0, 0, 0, 0], image[header.len()..header.len() + 9]);
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::{Display, Formatter};
+
+/// Directed graph of `before -> after` rules.
+///
+/// Rules don't have to form a total or even an acyclic order: only the nodes
+/// passed to `order` are considered, so a graph that is cyclic as a whole can
+/// still order every subset that avoids the cycle.
+pub struct RuleGraph<T> {
+    after: BTreeMap<T, BTreeSet<T>>,
+}
+
+/// Topological order of a set of nodes, `unique` if no other order satisfies the rules.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TopoOrder<T> {
+    pub order: Vec<T>,
+    pub unique: bool,
+}
+
+/// Nodes of a cycle, the first one repeated at the end.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cycle<T>(pub Vec<T>);
+
+impl<T: Display> Display for Cycle<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let nodes = self.0.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        write!(f, "cycle {}", nodes.join(" -> "))
+    }
+}
+
+/// Why a list of nodes can't be ordered.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OrderError<T> {
+    /// The node occurs more than once.
+    Repeated(T),
+    Cycle(Cycle<T>),
+}
+
+impl<T: Display> Display for OrderError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderError::Repeated(node) => write!(f, "{} occurs more than once", node),
+            OrderError::Cycle(cycle) => write!(f, "{}", cycle),
+        }
+    }
+}
+
+impl<T: Copy + Ord + std::hash::Hash> RuleGraph<T> {
+    pub fn new(rules: impl IntoIterator<Item=(T, T)>) -> Self {
+        let mut after: BTreeMap<T, BTreeSet<T>> = BTreeMap::new();
+        for (a, b) in rules {
+            after.entry(a).or_default().insert(b);
+        }
+        RuleGraph {
+            after
+        }
+    }
+
+    pub fn has_rule(&self, before: T, after: T) -> bool {
+        self.after.get(&before).is_some_and(|next| next.contains(&after))
+    }
+
+    /// Any cycle among all the nodes of the graph.
+    pub fn cycle(&self) -> Option<Cycle<T>> {
+        let nodes = self.after.iter()
+            .flat_map(|(a, next)| std::iter::once(*a).chain(next.iter().copied()))
+            .collect::<HashSet<_>>();
+        self.find_cycle(&nodes)
+    }
+
+    /// Kahn's algorithm over the rules between `nodes`, which must be distinct.
+    /// Picks the smallest available node first, so the result is deterministic;
+    /// the order is unique when there is never more than one to pick from.
+    pub fn order(&self, nodes: &[T]) -> Result<TopoOrder<T>, OrderError<T>> {
+        let mut set = HashSet::new();
+        if let Some(&node) = nodes.iter().find(|&&n| !set.insert(n)) {
+            return Err(OrderError::Repeated(node));
+        }
+        let mut incoming: BTreeMap<T, usize> = nodes.iter().map(|&n| (n, 0)).collect();
+        for &n in nodes {
+            for next in self.successors(n, &set) {
+                *incoming.get_mut(&next).expect("successor is a node") += 1;
+            }
+        }
+        let mut ready = incoming.iter().filter(|(_, &c)| c == 0).map(|(&n, _)| n).collect::<BTreeSet<_>>();
+        let mut order = Vec::with_capacity(nodes.len());
+        let mut unique = true;
+        while let Some(n) = ready.pop_first() {
+            unique &= ready.is_empty();
+            order.push(n);
+            for next in self.successors(n, &set) {
+                let count = incoming.get_mut(&next).expect("successor is a node");
+                *count -= 1;
+                if *count == 0 {
+                    ready.insert(next);
+                }
+            }
+        }
+        if order.len() < nodes.len() {
+            let left = set.into_iter().filter(|n| !order.contains(n)).collect::<HashSet<_>>();
+            // with distinct nodes whatever Kahn's algorithm leaves over lies on a cycle
+            return Err(OrderError::Cycle(self.find_cycle(&left).expect("unordered nodes contain a cycle")));
+        }
+        Ok(TopoOrder {
+            order,
+            unique,
+        })
+    }
+
+    /// Positions `(i, j)`, `i < j`, where `nodes[j]` has a rule to come before `nodes[i]`.
+    pub fn violations(&self, nodes: &[T]) -> Vec<(usize, usize)> {
+        (0..nodes.len())
+            .flat_map(|i| (i + 1..nodes.len()).map(move |j| (i, j)))
+            .filter(|&(i, j)| self.has_rule(nodes[j], nodes[i]))
+            .collect()
+    }
+
+    /// Fewest nodes that have to be moved elsewhere to satisfy the rules.
+    ///
+    /// Two nodes conflict when the later one must come before the earlier one,
+    /// directly or through other nodes of the list. Conflicts are transitive, so
+    /// by Dilworth's theorem the most nodes that can stay put (an antichain) is
+    /// `n` minus a maximum matching between conflicting pairs.
+    pub fn min_moves(&self, nodes: &[T]) -> usize {
+        let n = nodes.len();
+        let mut reach = vec![vec![false; n]; n];
+        for (i, j) in (0..n).flat_map(|i| (0..n).map(move |j| (i, j))) {
+            reach[i][j] = self.has_rule(nodes[i], nodes[j]);
+        }
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    reach[i][j] |= reach[i][k] && reach[k][j];
+                }
+            }
+        }
+        // conflict[i] holds the later positions that must come before i
+        let conflict = (0..n).map(|i| (i + 1..n).filter(|&j| reach[j][i]).collect::<Vec<_>>()).collect::<Vec<_>>();
+        let mut matched: Vec<Option<usize>> = vec![None; n];
+        (0..n).filter(|&i| augment(i, &conflict, &mut matched, &mut vec![false; n])).count()
+    }
+
+    fn successors<'a>(&'a self, n: T, nodes: &'a HashSet<T>) -> impl Iterator<Item=T> + 'a {
+        self.after.get(&n).into_iter().flatten().copied().filter(|next| nodes.contains(next))
+    }
+
+    // depth first search, a cycle closes when reaching a node still on the path
+    fn find_cycle(&self, nodes: &HashSet<T>) -> Option<Cycle<T>> {
+        let mut done: HashSet<T> = HashSet::new();
+        let mut starts = nodes.iter().copied().collect::<Vec<_>>();
+        starts.sort();
+        for start in starts {
+            if done.contains(&start) {
+                continue;
+            }
+            let mut path = vec![start];
+            let mut stack = vec![self.successors(start, nodes).collect::<Vec<_>>()];
+            while let Some(next) = stack.last_mut() {
+                match next.pop() {
+                    Some(n) if path.contains(&n) => {
+                        let from = path.iter().position(|p| *p == n).expect("node is on the path");
+                        let mut cycle = path[from..].to_vec();
+                        cycle.push(n);
+                        return Some(Cycle(cycle));
+                    }
+                    Some(n) if !done.contains(&n) => {
+                        path.push(n);
+                        stack.push(self.successors(n, nodes).collect());
+                    }
+                    Some(_) => {}
+                    None => {
+                        done.insert(path.pop().expect("path follows the stack"));
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+// Kuhn's augmenting path search for bipartite matching
+fn augment(i: usize, edges: &[Vec<usize>], matched: &mut [Option<usize>], seen: &mut [bool]) -> bool {
+    for &j in &edges[i] {
+        if seen[j] {
+            continue;
+        }
+        seen[j] = true;
+        if matched[j].is_none_or(|other| augment(other, edges, matched, seen)) {
+            matched[j] = Some(i);
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order() {
+        let graph = RuleGraph::new([(1, 2), (2, 3), (1, 3), (4, 3)]);
+        assert_eq!(Ok(TopoOrder { order: vec![1, 2, 3], unique: true }), graph.order(&[3, 1, 2]));
+        assert_eq!(Ok(TopoOrder { order: vec![1, 2, 4, 3], unique: false }), graph.order(&[4, 3, 2, 1]));
+        assert!(graph.has_rule(4, 3));
+        assert_eq!(None, graph.cycle());
+        assert_eq!(Err(OrderError::Repeated(1)), graph.order(&[1, 2, 1]));
+        assert_eq!("1 occurs more than once", OrderError::Repeated(1).to_string());
+    }
+
+    #[test]
+    fn test_cycle() {
+        let graph = RuleGraph::new([(1, 2), (2, 3), (3, 1), (3, 4)]);
+        assert_eq!(Some(Cycle(vec![1, 2, 3, 1])), graph.cycle());
+        assert_eq!("cycle 1 -> 2 -> 3 -> 1", graph.cycle().unwrap().to_string());
+        assert_eq!(Err(OrderError::Cycle(Cycle(vec![1, 2, 3, 1]))), graph.order(&[4, 3, 2, 1]));
+        assert!(graph.order(&[1, 3, 4]).is_ok());
+    }
+
+    #[test]
+    fn test_moves() {
+        let graph = RuleGraph::new([(1, 2), (2, 3)]);
+        assert_eq!(vec![(0, 2)], graph.violations(&[3, 1, 2]));
+        // 1 doesn't rule out 3 directly, but has to precede it through 2
+        assert_eq!(1, graph.min_moves(&[3, 1, 2]));
+        assert_eq!(2, graph.min_moves(&[3, 2, 1]));
+        assert_eq!(0, graph.min_moves(&[1, 2, 3]));
+    }
+}
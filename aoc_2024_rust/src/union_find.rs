@@ -0,0 +1,117 @@
+/// Per-component value that is combined when two components are joined.
+pub trait Merge {
+    fn merge(&mut self, other: Self);
+}
+
+impl Merge for () {
+    fn merge(&mut self, _other: Self) {}
+}
+
+/// Disjoint-set forest with path compression and union by rank.
+/// Every root also carries its component size and a `Merge` aggregate.
+pub struct UnionFind<T = ()> {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    size: Vec<usize>,
+    data: Vec<T>,
+}
+
+impl UnionFind {
+    pub fn new(n: usize) -> Self {
+        UnionFind::from_data(vec![(); n])
+    }
+}
+
+impl<T: Merge + Default> UnionFind<T> {
+    pub fn from_data(data: Vec<T>) -> Self {
+        let n = data.len();
+        UnionFind {
+            parent: (0..n).collect(),
+            rank: vec![0; n],
+            size: vec![1; n],
+            data,
+        }
+    }
+
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut curr = x;
+        while self.parent[curr] != root {
+            let next = self.parent[curr];
+            self.parent[curr] = root;
+            curr = next;
+        }
+        root
+    }
+
+    /// Joins the components of `a` and `b`, returns false if they were already joined.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.rank[a] < self.rank[b] {
+            (a, b) = (b, a);
+        }
+        if self.rank[a] == self.rank[b] {
+            self.rank[a] += 1;
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        let other = std::mem::take(&mut self.data[b]);
+        self.data[a].merge(other);
+        true
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Root, size and aggregate of every component.
+    pub fn roots(&self) -> impl Iterator<Item=(usize, usize, &T)> {
+        (0..self.parent.len())
+            .filter(|&x| self.parent[x] == x)
+            .map(|x| (x, self.size[x], &self.data[x]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default, Debug, Eq, PartialEq)]
+    struct Sum(i64);
+
+    impl Merge for Sum {
+        fn merge(&mut self, other: Self) {
+            self.0 += other.0
+        }
+    }
+
+    #[test]
+    fn test_union_find() {
+        let mut uf = UnionFind::new(5);
+        assert!(uf.union(0, 1));
+        assert!(uf.union(3, 4));
+        assert!(!uf.union(1, 0));
+        assert!(uf.connected(0, 1));
+        assert!(!uf.connected(1, 3));
+        let root = uf.find(4);
+        let sizes = uf.roots().map(|(r, size, _)| (r, size)).collect::<Vec<_>>();
+        assert_eq!(3, sizes.len());
+        assert!(sizes.contains(&(root, 2)));
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let mut uf = UnionFind::from_data((1..=4).map(Sum).collect());
+        uf.union(0, 2);
+        uf.union(2, 3);
+        let mut sums = uf.roots().map(|(_, size, sum)| (size, sum.0)).collect::<Vec<_>>();
+        sums.sort();
+        assert_eq!(vec![(1, 2), (3, 8)], sums);
+    }
+}